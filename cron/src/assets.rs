@@ -6,24 +6,34 @@ use cw_asset::AssetInfoBase;
 use cw_orch::prelude::*;
 use serde_json::{from_value, Value};
 
-use crate::{batch_execute_ans, EntryDif};
+use crate::{batch_ans_msgs, EntryDif, ScrapedFiles};
 
 pub fn get_scraped_entries(
+    files: &ScrapedFiles,
     chain_name: &str,
     chain_id: &str,
 ) -> Result<HashMap<String, AssetInfoBase<String>>, AbstractInterfaceError> {
-    let raw_scraped_entries = crate::get_scraped_json_data("assets");
+    let raw_scraped_entries = files.json("assets")?;
 
-    let parsed_scraped_entries: Vec<Vec<Value>> =
-        from_value(raw_scraped_entries[chain_name][chain_id].clone()).unwrap();
+    parse_scraped_entries(&raw_scraped_entries[chain_name][chain_id])
+}
+
+/// Parse the `[name, asset_info]` entries of one chain section of the scraped assets
+pub fn parse_scraped_entries(
+    section: &Value,
+) -> Result<HashMap<String, AssetInfoBase<String>>, AbstractInterfaceError> {
+    let parsed_scraped_entries: Vec<Vec<Value>> = from_value(section.clone())?;
 
     let scraped_entries_vec: Vec<(String, AssetInfoBase<String>)> = parsed_scraped_entries
         .into_iter()
         .map(|v| {
-            let asset_info: AssetInfoBase<String> = from_value(v[1].clone()).unwrap();
-            (v[0].as_str().unwrap().to_owned(), asset_info)
+            let asset_info: AssetInfoBase<String> = from_value(v[1].clone())?;
+            let name = v[0]
+                .as_str()
+                .ok_or_else(|| CwOrchError::StdErr(format!("invalid asset name {}", v[0])))?;
+            Ok((name.to_owned(), asset_info))
         })
-        .collect();
+        .collect::<Result<_, AbstractInterfaceError>>()?;
 
     Ok(scraped_entries_vec.into_iter().collect())
 }
@@ -45,26 +55,26 @@ pub fn get_on_chain_entries(
     Ok(on_chain_entries)
 }
 
-pub fn update(
-    ans_host: &AnsHost<Daemon>,
-    diff: EntryDif<String, AssetInfoBase<String>>,
-) -> Result<(), AbstractInterfaceError> {
+pub fn update_msgs(diff: EntryDif<String, AssetInfoBase<String>>) -> Vec<ExecuteMsg> {
     let to_add: Vec<_> = diff.1.into_iter().collect();
     let to_remove: Vec<_> = diff.0.into_iter().collect();
+    let batch_size = crate::config::get().batch_sizes.assets.get();
 
     // add the assets
-    batch_execute_ans(&ans_host, &to_add, 25, |chunk| ExecuteMsg::UpdateAssetAddresses {
+    let mut msgs = batch_ans_msgs(&to_add, batch_size, |chunk| ExecuteMsg::UpdateAssetAddresses {
         to_add: chunk.to_vec(),
         to_remove: vec![],
-    })?;
+    });
 
     // remove the assets
-    batch_execute_ans(&ans_host, &to_remove, 25, |chunk| ExecuteMsg::UpdateAssetAddresses {
-        to_add: vec![],
-        to_remove: chunk.to_vec(),
-    })?;
+    msgs.extend(batch_ans_msgs(&to_remove, batch_size, |chunk| {
+        ExecuteMsg::UpdateAssetAddresses {
+            to_add: vec![],
+            to_remove: chunk.to_vec(),
+        }
+    }));
 
-    Ok(())
+    msgs
 }
 
 #[cfg(test)]
@@ -79,6 +89,7 @@ mod test {
     use tokio::runtime::Runtime;
 
     use super::{get_on_chain_entries, get_scraped_entries};
+    use crate::ScrapedFiles;
 
     const CHAIN: ChainInfo = JUNO_1;
 
@@ -89,7 +100,8 @@ mod test {
         let chain_name = chain.chain_name;
         let chain_id = chain.chain_id.to_string();
 
-        let scraped = get_scraped_entries(&chain_name, &chain_id).unwrap();
+        let files = ScrapedFiles::fetch(&["assets"]).unwrap();
+        let scraped = get_scraped_entries(&files, &chain_name, &chain_id).unwrap();
 
         println!("scraped: {scraped:?}");
 
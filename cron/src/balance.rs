@@ -0,0 +1,172 @@
+//! Wallet balance preflight: whether the sender can pay the fees of a run on every chain
+
+use std::fmt;
+
+use abstract_interface::AbstractInterfaceError;
+use cosmos_sdk_proto::cosmos::bank::v1beta1::{
+    query_client::QueryClient as BankQueryClient, QueryBalanceRequest,
+};
+use cw_orch::prelude::*;
+use cw_orch::state::ChainState;
+use serde::Serialize;
+
+/// Balance of the sender of a chain against the fees of the transactions a run broadcasts there
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BalanceCheck {
+    pub chain_id: String,
+    pub address: String,
+    pub fee_denom: String,
+    pub transactions: usize,
+    pub required: u128,
+    pub available: u128,
+}
+
+impl BalanceCheck {
+    pub fn is_short(&self) -> bool {
+        self.available < self.required
+    }
+}
+
+/// Fee of `transactions` transactions of `gas_per_transaction` gas at `gas_price`
+pub fn required_fee(transactions: usize, gas_per_transaction: u64, gas_price: f64) -> u128 {
+    (transactions as f64 * gas_per_transaction as f64 * gas_price).ceil() as u128
+}
+
+/// Compare the balance of the sender of `chain` with the fee of `transactions` transactions.
+/// The daemon pays its fees in the first fee token of the chain, which `gas_denom` of the config selects.
+pub fn check_balance(
+    chain: &Daemon,
+    transactions: usize,
+) -> Result<BalanceCheck, AbstractInterfaceError> {
+    let chain_data = &chain.state().chain_data;
+    let chain_id = chain_data.chain_id.to_string();
+    let fee_token = chain_data
+        .fees
+        .fee_tokens
+        .first()
+        .ok_or_else(|| CwOrchError::StdErr(format!("{chain_id} has no fee token")))?;
+    let address = chain.sender().to_string();
+
+    let balance = chain
+        .rt_handle
+        .block_on(
+            BankQueryClient::new(chain.channel()).balance(QueryBalanceRequest {
+                address: address.clone(),
+                denom: fee_token.denom.clone(),
+            }),
+        )
+        .map_err(|status| {
+            CwOrchError::StdErr(format!("balance query failed: {}", status.message()))
+        })?;
+    // An account without any balance of the denom has no coin at all
+    let available = match balance.into_inner().balance {
+        Some(coin) => coin.amount.parse().map_err(|e| {
+            CwOrchError::StdErr(format!("invalid balance amount {}: {e}", coin.amount))
+        })?,
+        None => 0,
+    };
+
+    Ok(BalanceCheck {
+        chain_id,
+        address,
+        fee_denom: fee_token.denom.clone(),
+        transactions,
+        required: required_fee(
+            transactions,
+            crate::config::get().gas_per_transaction,
+            fee_token.fixed_min_gas_price,
+        ),
+        available,
+    })
+}
+
+/// The balance checks of a run as one table, chains whose balance couldn't be checked show their error
+pub struct BalanceTable<'a>(pub &'a [(String, Result<BalanceCheck, String>)]);
+
+impl fmt::Display for BalanceTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header = [
+            "chain",
+            "address",
+            "denom",
+            "txs",
+            "required",
+            "available",
+            "status",
+        ];
+        let mut rows = vec![header.map(str::to_string).to_vec()];
+        for (chain_id, result) in self.0 {
+            rows.push(match result {
+                Ok(check) => vec![
+                    check.chain_id.clone(),
+                    check.address.clone(),
+                    check.fee_denom.clone(),
+                    check.transactions.to_string(),
+                    check.required.to_string(),
+                    check.available.to_string(),
+                    if check.is_short() { "SHORT" } else { "ok" }.to_string(),
+                ],
+                Err(err) => {
+                    let mut row = vec![chain_id.clone()];
+                    row.extend(vec!["-".to_string(); header.len() - 2]);
+                    row.push(format!("error: {err}"));
+                    row
+                }
+            });
+        }
+
+        let widths: Vec<usize> = (0..header.len())
+            .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+            .collect();
+        for row in rows {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect();
+            writeln!(f, "{}", cells.join("  ").trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check(required: u128, available: u128) -> BalanceCheck {
+        BalanceCheck {
+            chain_id: "juno-1".to_string(),
+            address: "juno1sender".to_string(),
+            fee_denom: "ujuno".to_string(),
+            transactions: 2,
+            required,
+            available,
+        }
+    }
+
+    #[test]
+    fn computes_required_fees() {
+        assert_eq!(required_fee(2, 1_000_000, 0.075), 150_000);
+        assert_eq!(required_fee(0, 1_000_000, 0.075), 0);
+        assert!(check(150_000, 149_999).is_short());
+        assert!(!check(150_000, 150_000).is_short());
+    }
+
+    #[test]
+    fn renders_one_row_per_chain() {
+        let results = vec![
+            ("juno-1".to_string(), Ok(check(150_000, 10))),
+            ("osmosis-1".to_string(), Err("unreachable".to_string())),
+        ];
+
+        let table = BalanceTable(&results).to_string();
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("chain"));
+        assert!(lines[1].ends_with("SHORT"));
+        assert!(lines[2].starts_with("osmosis-1"));
+        assert!(lines[2].ends_with("error: unreachable"));
+    }
+}
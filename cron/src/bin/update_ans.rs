@@ -1,71 +1,1198 @@
-use abstract_interface::Abstract;
+use std::collections::BTreeSet;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+
+use abstract_core::ans_host::ExecuteMsg;
+use abstract_core::ANS_HOST;
+use abstract_interface::{Abstract, AbstractInterfaceError, AnsHost};
+use clap::{Parser, Subcommand};
 use cw_orch::{
     deploy::Deploy,
     prelude::{
         *,
-        networks::{ChainInfo, parse_network},
+        networks::ChainInfo,
     },
 };
-use cw_orch::daemon::ChainKind;
-use cw_orch::daemon::networks::neutron::NEUTRON_NETWORK;
+use cw_orch::daemon::{ChainKind, ChainRegistryData as ChainData};
+use script_helpers::balance::{self, BalanceCheck, BalanceTable};
+use script_helpers::config::{self, Config};
+use script_helpers::execution::{Execution, ProposalConfig, ProposalKind};
+use script_helpers::journal::{snapshot_path, Journal};
+use script_helpers::parallel::run_concurrently;
+use script_helpers::plan::{self, ChainPlan, Plan};
+use script_helpers::rollback::{self, Snapshot, TouchedKeys};
+use script_helpers::verify::VerificationReport;
+use script_helpers::safety::{self, RemovalLimits};
+use script_helpers::sections;
+use script_helpers::validation::consistency::{self, FindingKind};
+use script_helpers::validation::report::{self, EntryPositions, ValidationReport};
+use script_helpers::validation::{self, Diagnostic, Severity};
+use script_helpers::{
+    AnsData, AnsDataDiff, EntityType, FetchError, ScrapedFiles, ANS_DATA_ENTITIES,
+};
 use tokio::runtime::Runtime;
 
-/// <https://github.com/cosmos/chain-registry/blob/master/neutron/chain.json>
-pub const NEUTRON_1: ChainInfo = ChainInfo {
-    kind: ChainKind::Mainnet,
-    chain_id: "neutron-1",
-    gas_denom: "untrn",
-    gas_price: 0.25,
-    grpc_urls: &["http://grpc-kralum.neutron-1.neutron.org:80"],
-    network_info: NEUTRON_NETWORK,
-    lcd_url: Some("https://rest-kralum.neutron-1.neutron.org"),
-    fcd_url: None,
-};
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Arguments {
+    /// Updater config file, the bundled `updater.toml` is used when not set
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Base URL or local directory of the scraped ANS data, overrides the config
+    #[arg(long)]
+    scrape_url: Option<String>,
+    /// Channels file, overrides the config
+    #[arg(long)]
+    channels_path: Option<String>,
+    /// Gas the balance preflight reserves for every transaction, overrides the config
+    #[arg(long)]
+    gas_per_transaction: Option<u64>,
+    /// Contracts per ANS host message, overrides the config
+    #[arg(long)]
+    batch_size_contracts: Option<NonZeroUsize>,
+    /// Assets per ANS host message, overrides the config
+    #[arg(long)]
+    batch_size_assets: Option<NonZeroUsize>,
+    /// Dexes per ANS host message, overrides the config
+    #[arg(long)]
+    batch_size_dexes: Option<NonZeroUsize>,
+    /// Pools per ANS host message, overrides the config
+    #[arg(long)]
+    batch_size_pools: Option<NonZeroUsize>,
+    /// Channels per ANS host message, overrides the config
+    #[arg(long)]
+    batch_size_channels: Option<NonZeroUsize>,
+    /// What to do with chains where the sender can't pay the fees of the update
+    #[arg(long, value_enum, default_value_t = BalancePolicy::Abort)]
+    balance_policy: BalancePolicy,
+    /// Chain ids to update, comma separated
+    #[arg(long, value_delimiter = ',', conflicts_with = "all_deployed")]
+    chains: Vec<String>,
+    /// Update every chain Abstract is deployed on
+    #[arg(long)]
+    all_deployed: bool,
+    /// Selected chains the scraped data doesn't resolve to, they are left out of the run
+    #[arg(skip)]
+    unresolved_chains: Vec<String>,
+    /// Only keep the mainnet chains of the selection
+    #[arg(long, conflicts_with = "testnet")]
+    mainnet: bool,
+    /// Only keep the testnet chains of the selection
+    #[arg(long)]
+    testnet: bool,
+    /// Only sync these entity types, comma separated. Channels are only synced when selected here
+    #[arg(long, value_enum, value_delimiter = ',')]
+    only: Vec<EntityType>,
+    /// Wrap the ANS updates in a proposal to this multisig or DAO proposal module instead of executing them
+    #[arg(long)]
+    proposal_contract: Option<String>,
+    /// Kind of contract the proposal is sent to
+    #[arg(long, value_enum, default_value_t = ProposalKind::Cw3)]
+    proposal_kind: ProposalKind,
+    /// Title of the proposal
+    #[arg(long, default_value = "Update Abstract Name Service")]
+    proposal_title: String,
+    /// Description of the proposal
+    #[arg(long, default_value = "Sync the ANS host with the scraped ANS data")]
+    proposal_description: String,
+    /// Submit the proposal from the sender's key instead of printing it
+    #[arg(long)]
+    submit: bool,
+    /// Execute the ANS updates on behalf of this admin address through an authz grant to the sender
+    #[arg(long, conflicts_with = "proposal_contract")]
+    authz_granter: Option<String>,
+    /// Directory the run journal is written to
+    #[arg(long, default_value = "journal")]
+    journal_dir: String,
+    /// Maximum number of entries of one entity type a run may remove on a chain
+    #[arg(long, default_value_t = RemovalLimits::default().max_removals)]
+    max_removals: usize,
+    /// Maximum share of the on-chain entries of one entity type a run may remove on a chain, in percent
+    #[arg(long, default_value_t = RemovalLimits::default().max_removal_percent)]
+    max_removal_percent: f64,
+    /// Entity types with fewer on-chain entries are only held to `--max-removals`
+    #[arg(long, default_value_t = RemovalLimits::default().min_on_chain_for_percent)]
+    min_on_chain_for_percent: usize,
+    /// Apply removals that exceed the removal limits
+    #[arg(long)]
+    allow_large_removals: bool,
+    /// Number of chains that are processed at the same time
+    #[arg(long, default_value_t = 4)]
+    concurrency: usize,
+    /// Don't query the ANS host again after the update to check that it's in sync
+    #[arg(long)]
+    skip_verify: bool,
+    /// Query the chain for the tokens and contracts of every added entry and refuse the update when any is missing
+    #[arg(long)]
+    check_existence: bool,
+    /// Refuse scraped data that lists an entry twice or an asset denom under several names, instead of warning
+    #[arg(long)]
+    strict_duplicates: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// What the update does when the sender can't pay the fees of a chain, or its balance can't be checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum BalancePolicy {
+    /// Stop before anything is broadcast
+    Abort,
+    /// Leave those chains out and update the others
+    Skip,
+    /// Don't check the balances
+    Ignore,
+}
 
-fn update_ans() -> anyhow::Result<()> {
+/// Outcome of the `check` command, used as exit code. A worse outcome has a higher code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum CheckStatus {
+    InSync = 0,
+    Drift = 1,
+    DataError = 2,
+    ChainError = 3,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Sync the ANS hosts with the scraped data (default)
+    Update,
+    /// Report whether the ANS hosts drifted from the scraped data without broadcasting anything.
+    /// Exits with 0 when in sync, 1 on drift, 2 on a data or config error and 3 on a chain or query error
+    Check,
+    /// Validate the scraped data of the selected chains and write the diagnostics to a SARIF report.
+    /// Fails when any diagnostic is an error
+    Validate {
+        /// Where the report is written to
+        #[arg(long, default_value = "validation-report.sarif.json")]
+        report: PathBuf,
+    },
+    /// Compare the IBC assets of all scraped chains with the assets of their origin chains
+    /// and write the findings to a JSON report. Only reports, never fails on findings
+    Consistency {
+        /// Where the report is written to
+        #[arg(long, default_value = "consistency-report.json")]
+        out: PathBuf,
+    },
+    /// Compute the changes for the selected chains and write them to a plan file
+    Plan {
+        /// Where the plan is written to
+        #[arg(long, default_value = "ans-plan.json")]
+        out: PathBuf,
+    },
+    /// Upload the scraped data to a freshly deployed ANS host. Nothing is removed, the entries are sent
+    /// in dependency order in the largest batches a transaction fits, then the host is verified
+    Bootstrap {
+        /// Address of the ANS host, for deployments that aren't in the state file. Needs a single chain
+        #[arg(long, required_unless_present = "state", conflicts_with = "state")]
+        ans_host: Option<String>,
+        /// cw-orch state file of the deployment, the ANS host of every selected chain is read from it
+        #[arg(long)]
+        state: Option<PathBuf>,
+    },
+    /// Execute exactly the messages of a plan file
+    Apply {
+        /// Plan file written by the `plan` command
+        #[arg(long)]
+        plan: PathBuf,
+    },
+    /// Undo a previous run from its journal, or restore a snapshot of the on-chain ANS state
+    Rollback {
+        /// Journal of the run to undo, only the entries it touched are restored
+        #[arg(long, required_unless_present = "snapshot", conflicts_with = "snapshot")]
+        journal: Option<PathBuf>,
+        /// Snapshot of the on-chain ANS state to restore completely
+        #[arg(long)]
+        snapshot: Option<PathBuf>,
+    },
+}
+
+impl Arguments {
+    /// The config file with the command line overrides applied
+    fn config(&self) -> anyhow::Result<Config> {
+        let mut config = match &self.config {
+            Some(path) => Config::load(path)?,
+            None => Config::default(),
+        };
+        if let Some(scrape_url) = &self.scrape_url {
+            config.scrape_url = scrape_url.clone();
+        }
+        if let Some(channels_path) = &self.channels_path {
+            config.channels_path = channels_path.clone();
+        }
+        if let Some(gas_per_transaction) = self.gas_per_transaction {
+            config.gas_per_transaction = gas_per_transaction;
+        }
+        let batch_sizes = &mut config.batch_sizes;
+        for (batch_size, size_override) in [
+            (&mut batch_sizes.contracts, self.batch_size_contracts),
+            (&mut batch_sizes.assets, self.batch_size_assets),
+            (&mut batch_sizes.dexes, self.batch_size_dexes),
+            (&mut batch_sizes.pools, self.batch_size_pools),
+            (&mut batch_sizes.channels, self.batch_size_channels),
+        ] {
+            *batch_size = size_override.unwrap_or(*batch_size);
+        }
+        Ok(config)
+    }
+
+    /// The chains selected with `--chains` or `--all-deployed`, filtered by kind
+    fn networks(&self) -> anyhow::Result<Vec<ChainInfo>> {
+        let chain_ids = if self.all_deployed {
+            Abstract::<Daemon>::get_all_deployed_chains()
+        } else {
+            self.chains.clone()
+        };
+        if chain_ids.is_empty() {
+            anyhow::bail!("no chains selected, use --chains or --all-deployed");
+        }
+
+        let networks = chain_ids
+            .iter()
+            .map(|chain_id| network(chain_id))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(networks
+            .into_iter()
+            .filter(|network| self.kind_selected(&network.kind))
+            .filter(|network| !self.unresolved_chains.iter().any(|id| id == network.chain_id))
+            .collect())
+    }
+
+    fn entities(&self) -> Vec<EntityType> {
+        if self.only.is_empty() {
+            ANS_DATA_ENTITIES.to_vec()
+        } else {
+            self.only.clone()
+        }
+    }
+
+    /// Refuse diffs that remove more than the removal limits allow, unless explicitly allowed
+    fn check_removals(
+        &self,
+        chain_id: &str,
+        diff: &AnsDataDiff,
+        on_chain_entries: &AnsData,
+    ) -> anyhow::Result<()> {
+        let limits = RemovalLimits {
+            max_removals: self.max_removals,
+            max_removal_percent: self.max_removal_percent,
+            min_on_chain_for_percent: self.min_on_chain_for_percent,
+        };
+        if !self.allow_large_removals {
+            safety::assert_removals(chain_id, diff, on_chain_entries, &limits)?;
+            return Ok(());
+        }
+        for violation in safety::check_removals(diff, on_chain_entries, &limits) {
+            log::warn!("{}: applying large removal, {}", chain_id, violation);
+        }
+        Ok(())
+    }
+
+    /// Tokens and contracts `diff` registers that don't exist on-chain, when enabled.
+    /// A query that fails for another reason is returned as an error instead of a diagnostic.
+    fn existence_diagnostics(
+        &self,
+        ans_host: &AnsHost<Daemon>,
+        diff: &AnsDataDiff,
+    ) -> Result<Vec<Diagnostic>, AbstractInterfaceError> {
+        if !self.check_existence {
+            return Ok(vec![]);
+        }
+        validation::existence::validate_existence(ans_host, diff)
+    }
+
+    /// Refuse diffs that register tokens or contracts that don't exist on-chain, when enabled
+    fn check_existence(
+        &self,
+        chain_id: &str,
+        ans_host: &AnsHost<Daemon>,
+        diff: &AnsDataDiff,
+    ) -> Result<(), AbstractInterfaceError> {
+        validation::assert_valid(chain_id, &self.existence_diagnostics(ans_host, diff)?)
+    }
+
+    fn kind_selected(&self, kind: &ChainKind) -> bool {
+        match kind {
+            ChainKind::Mainnet => !self.testnet,
+            ChainKind::Testnet => !self.mainnet,
+            ChainKind::Local => !self.mainnet && !self.testnet,
+        }
+    }
+
+    fn execution(&self, chain_id: &str) -> Execution {
+        if let Some(granter) = &self.authz_granter {
+            return Execution::Authz {
+                granter: granter.clone(),
+            };
+        }
+        match &self.proposal_contract {
+            None => Execution::Direct,
+            Some(proposal_contract) => Execution::Proposal(ProposalConfig {
+                kind: self.proposal_kind,
+                proposal_contract: proposal_contract.clone(),
+                title: format!("{} ({})", self.proposal_title, chain_id),
+                description: self.proposal_description.clone(),
+                submit: self.submit,
+            }),
+        }
+    }
+}
+
+fn network(chain_id: &str) -> anyhow::Result<ChainInfo<'static>> {
+    Ok(config::get().network(chain_id)?)
+}
+
+fn daemon(rt: &Runtime, network: ChainInfo) -> anyhow::Result<Daemon> {
+    Ok(DaemonBuilder::default()
+        .handle(rt.handle())
+        .chain(config::get().chain_data(network))
+        .build()?)
+}
+
+fn load_ans_host(rt: &Runtime, network: ChainInfo) -> anyhow::Result<AnsHost<Daemon>> {
+    let deployment = Abstract::load_from(daemon(rt, network)?)?;
+    Ok(deployment.ans_host)
+}
+
+/// The ANS host at `address` instead of the one of the deployment in the state file
+fn ans_host_at(rt: &Runtime, network: ChainInfo, address: &str) -> anyhow::Result<AnsHost<Daemon>> {
+    let ans_host = AnsHost::new(ANS_HOST, daemon(rt, network)?);
+    ans_host.set_address(&Addr::unchecked(address));
+    Ok(ans_host)
+}
+
+fn update_ans(args: &Arguments, files: &ScrapedFiles) -> anyhow::Result<()> {
     let rt = Runtime::new()?;
-    // let deployment = Abstract::load_from(Mock::new(&Addr::unchecked("input")))?;
-    // let chain_ids = deployment.get_all_deployed_chains();
-    let chain_ids: Vec<String> = vec!["osmo-test-5"]
-        .into_iter()
-        .map(|n| n.to_string())
+    let journal = Journal::new(&args.journal_dir)?;
+    log::info!("Recording run journal at {}", journal.path().display());
+    let entities = args.entities();
+    let networks = args.networks()?;
+
+    // Every chain is validated and diffed once, before anything is broadcast anywhere
+    let chain_ids = networks.iter().map(|network| network.chain_id);
+    let prepared = run_concurrently(networks.clone(), args.concurrency, |network| {
+        prepare_chain(args, &rt, files, network, &entities)
+    });
+    let prepared: Vec<(&str, anyhow::Result<PreparedChain>)> = chain_ids
+        .zip(prepared)
+        .map(|(chain_id, result)| {
+            let chain = result.map_err(anyhow::Error::msg).and_then(|chain| chain);
+            (chain_id, chain)
+        })
         .collect();
+    let runs = prepared
+        .iter()
+        .filter_map(|(_, chain)| chain.as_ref().ok())
+        .map(|chain| (chain.network.clone(), chain.msgs.len()))
+        .collect();
+    let unfunded = preflight_balances(args, &rt, runs)?;
+    let (chain_ids, prepared): (Vec<&str>, Vec<_>) = prepared
+        .into_iter()
+        .filter(|(chain_id, _)| !unfunded.iter().any(|unfunded| unfunded == *chain_id))
+        .unzip();
+    log::info!(
+        "Updating ANS on {}, {} chains at a time",
+        chain_ids.join(", "),
+        args.concurrency
+    );
+
+    let results = run_concurrently(prepared, args.concurrency, |chain| {
+        update_chain(args, &rt, &journal, chain?, &entities)
+    });
+    print_summary(&chain_ids, results)
+}
+
+/// One failing chain doesn't stop the others, report them all at the end
+fn print_summary(
+    chain_ids: &[&str],
+    results: Vec<Result<anyhow::Result<()>, String>>,
+) -> anyhow::Result<()> {
+    let mut failed = 0;
+    println!("Summary:");
+    for (chain_id, result) in chain_ids.iter().zip(results) {
+        match result {
+            Ok(Ok(())) => println!("  {chain_id}: ok"),
+            Ok(Err(err)) => {
+                failed += 1;
+                println!("  {chain_id}: failed, {err:#}");
+            }
+            Err(panic) => {
+                failed += 1;
+                println!("  {chain_id}: panicked, {panic}");
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{} of {} chains failed", failed, chain_ids.len());
+    }
+    Ok(())
+}
 
-    let networks: Vec<ChainInfo> = chain_ids
+/// Check the balance of the sender on the chain of every run, a network and the number of ANS host
+/// messages sent there, print the balances as one table and apply the balance policy.
+/// Returns the chains to leave out, or errors out when the policy aborts.
+fn preflight_balances(
+    args: &Arguments,
+    rt: &Runtime,
+    runs: Vec<(ChainInfo, usize)>,
+) -> anyhow::Result<Vec<String>> {
+    if args.balance_policy == BalancePolicy::Ignore {
+        return Ok(vec![]);
+    }
+    let chain_ids: Vec<String> = runs
         .iter()
-        .map(|n| parse_network(n).unwrap())
+        .map(|(network, _)| network.chain_id.to_string())
         .collect();
+    let checks = run_concurrently(runs, args.concurrency, |(network, msgs)| {
+        preflight_chain(args, rt, network, msgs)
+    });
+    let results: Vec<(String, Result<BalanceCheck, String>)> = chain_ids
+        .into_iter()
+        .zip(checks)
+        .map(|(chain_id, result)| {
+            let check = result.and_then(|check| check.map_err(|err| format!("{err:#}")));
+            (chain_id, check)
+        })
+        .collect();
+    println!("Wallet balances:\n{}", BalanceTable(&results));
 
-    for network in networks {
-        let chain = DaemonBuilder::default()
-            .handle(rt.handle())
-            .chain(network)
-            .build()?;
+    let unfunded: Vec<String> = results
+        .into_iter()
+        .filter(|(_, check)| !matches!(check, Ok(check) if !check.is_short()))
+        .map(|(chain_id, _)| chain_id)
+        .collect();
+    if unfunded.is_empty() {
+        return Ok(unfunded);
+    }
+    if args.balance_policy == BalancePolicy::Abort {
+        anyhow::bail!(
+            "the sender can't pay the fees on {}, nothing was broadcast",
+            unfunded.join(", ")
+        );
+    }
+    log::warn!("skipping {}, the sender can't pay the fees there", unfunded.join(", "));
+    Ok(unfunded)
+}
 
-        let deployment = Abstract::load_from(chain)?;
+/// Balance of the sender against the fees of the transactions that send `msgs` ANS host messages.
+/// Channel updates aren't counted.
+fn preflight_chain(
+    args: &Arguments,
+    rt: &Runtime,
+    network: ChainInfo,
+    msgs: usize,
+) -> anyhow::Result<BalanceCheck> {
+    let transactions = args.execution(network.chain_id).transaction_count(msgs);
+    Ok(balance::check_balance(&daemon(rt, network)?, transactions)?)
+}
+
+/// A chain whose scraped data is validated and diffed with its ANS host, ready to be updated
+struct PreparedChain<'a> {
+    network: ChainInfo<'a>,
+    scraped_entries: AnsData,
+    on_chain_entries: AnsData,
+    msgs: Vec<ExecuteMsg>,
+}
 
-        // Take the assets, contracts, and pools from resources and upload them to the ans host
-        let ans_host = deployment.ans_host;
-        // First we get all values
-        let scraped_entries = script_helpers::get_scraped_entries(&ans_host)?;
-        let on_chain_entries = script_helpers::get_on_chain_entries(&ans_host)?;
+fn prepare_chain<'a>(
+    args: &Arguments,
+    rt: &Runtime,
+    files: &ScrapedFiles,
+    network: ChainInfo<'a>,
+    entities: &[EntityType],
+) -> anyhow::Result<PreparedChain<'a>> {
+    let chain_id = network.chain_id;
 
-        // Then we create a diff between the 2 objects
-        let diff = script_helpers::diff(scraped_entries, on_chain_entries)?;
+    // Take the assets, contracts, and pools from resources and upload them to the ans host
+    let ans_host = load_ans_host(rt, network.clone())?;
+    // First we get all values
+    let scraped_entries = script_helpers::get_scraped_entries(&ans_host, files, entities)?;
+    let diagnostics = validation::validate(
+        &ans_host,
+        files,
+        &scraped_entries,
+        entities,
+        args.strict_duplicates,
+    )?;
+    validation::assert_valid(chain_id, &diagnostics)?;
+    let on_chain_entries = script_helpers::get_on_chain_entries(&ans_host, entities)?;
 
-        // Finally we upload on-chain
-        script_helpers::update(&ans_host, diff)?;
+    // Then we create a diff between the 2 objects
+    let diff = script_helpers::diff(scraped_entries.clone(), on_chain_entries.clone())?;
+    args.check_removals(chain_id, &diff, &on_chain_entries)?;
+    args.check_existence(chain_id, &ans_host, &diff)?;
+    let msgs = script_helpers::update_msgs(diff);
+    // A missing grant is found before any chain is updated
+    if let Execution::Authz { granter } = &args.execution(chain_id) {
+        script_helpers::execution::assert_authz_grant(&ans_host, granter, msgs.len())?;
     }
+    Ok(PreparedChain {
+        network,
+        scraped_entries,
+        on_chain_entries,
+        msgs,
+    })
+}
+
+fn update_chain(
+    args: &Arguments,
+    rt: &Runtime,
+    journal: &Journal,
+    chain: PreparedChain,
+    entities: &[EntityType],
+) -> anyhow::Result<()> {
+    let chain_id = chain.network.chain_id;
+    let execution = args.execution(chain_id);
+    let ans_host = load_ans_host(rt, chain.network)?;
+    Snapshot::new(chain_id, &chain.on_chain_entries, entities)
+        .write(journal.snapshot_path(chain_id))?;
+
+    // Finally we upload on-chain
+    script_helpers::execution::execute(&ans_host, chain.msgs, &execution, Some(journal))?;
+    if entities.contains(&EntityType::Channels) {
+        script_helpers::channels::update_channels(&ans_host, &execution, Some(journal))?;
+    }
+
+    // A proposal still has to pass before anything changes on-chain
+    if args.skip_verify || matches!(execution, Execution::Proposal(_)) {
+        return Ok(());
+    }
+    verify_chain(chain_id, &ans_host, chain.scraped_entries, entities)
+}
+
+fn verify_chain(
+    chain_id: &str,
+    ans_host: &AnsHost<Daemon>,
+    scraped_entries: AnsData,
+    entities: &[EntityType],
+) -> anyhow::Result<()> {
+    let report = script_helpers::verify::verify(ans_host, scraped_entries, entities)?;
+    if !report.is_in_sync() {
+        anyhow::bail!(
+            "ANS host on {} is still out of sync after the update:\n{}",
+            chain_id,
+            report
+        );
+    }
+    log::info!("{}", report);
     Ok(())
 }
 
+fn bootstrap_ans(
+    args: &Arguments,
+    files: &ScrapedFiles,
+    ans_host: Option<String>,
+    state: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    if args.proposal_contract.is_some() {
+        anyhow::bail!("a bootstrap sends its batches one by one, it can't be a proposal");
+    }
+    if let Some(state) = &state {
+        // cw-orch reads the addresses of the deployment from the state file it's pointed at
+        std::env::set_var("STATE_FILE", state);
+    }
+    let networks = args.networks()?;
+    if ans_host.is_some() && networks.len() != 1 {
+        anyhow::bail!("--ans-host is an address on one chain, select exactly one chain");
+    }
+
+    let rt = Runtime::new()?;
+    let journal = Journal::new(&args.journal_dir)?;
+    log::info!("Recording run journal at {}", journal.path().display());
+    let entities = args.entities();
+    let chain_ids: Vec<&str> = networks.iter().map(|network| network.chain_id).collect();
+    log::info!("Bootstrapping ANS on {}", chain_ids.join(", "));
+
+    let results = run_concurrently(networks, args.concurrency, |network| {
+        bootstrap_chain(args, &rt, &journal, files, network, &entities, ans_host.as_deref())
+    });
+    print_summary(&chain_ids, results)
+}
+
+fn bootstrap_chain(
+    args: &Arguments,
+    rt: &Runtime,
+    journal: &Journal,
+    files: &ScrapedFiles,
+    network: ChainInfo,
+    entities: &[EntityType],
+    ans_host_address: Option<&str>,
+) -> anyhow::Result<()> {
+    let chain_id = network.chain_id;
+    let execution = args.execution(chain_id);
+    let ans_host = match ans_host_address {
+        Some(address) => ans_host_at(rt, network, address)?,
+        None => load_ans_host(rt, network)?,
+    };
+    let scraped_entries = script_helpers::get_scraped_entries(&ans_host, files, entities)?;
+    let diagnostics = validation::validate(
+        &ans_host,
+        files,
+        &scraped_entries,
+        entities,
+        args.strict_duplicates,
+    )?;
+    validation::assert_valid(chain_id, &diagnostics)?;
+    // A fresh host is empty, a host that was partially bootstrapped before only gets the missing entries
+    let on_chain_entries = script_helpers::get_on_chain_entries(&ans_host, entities)?;
+    Snapshot::new(chain_id, &on_chain_entries, entities).write(journal.snapshot_path(chain_id))?;
+    let diff = script_helpers::diff(scraped_entries.clone(), on_chain_entries)?;
+    args.check_existence(chain_id, &ans_host, &diff)?;
+    if let Execution::Authz { granter } = &execution {
+        let transactions = script_helpers::bootstrap::transaction_count(&diff);
+        script_helpers::execution::assert_authz_grant(&ans_host, granter, transactions)?;
+    }
+    let removals: usize = diff.summary().iter().map(|(_, _, removed)| removed).sum();
+    if removals > 0 {
+        log::warn!(
+            "{}: {} on-chain entries aren't in the scraped data, a bootstrap leaves them in place",
+            chain_id,
+            removals
+        );
+    }
+
+    script_helpers::bootstrap::bootstrap(&ans_host, diff, &execution, Some(journal))?;
+    if entities.contains(&EntityType::Channels) {
+        script_helpers::channels::update_channels(&ans_host, &execution, Some(journal))?;
+    }
+
+    if args.skip_verify {
+        return Ok(());
+    }
+    verify_chain(chain_id, &ans_host, scraped_entries, entities)
+}
+
+/// Compare the scraped data with every selected ANS host, never broadcasts
+fn check_ans(args: &Arguments, files: &ScrapedFiles) -> CheckStatus {
+    let networks = match args.networks() {
+        Ok(networks) => networks,
+        Err(err) => {
+            log::error!("{:#}", err);
+            return CheckStatus::DataError;
+        }
+    };
+    let rt = match Runtime::new() {
+        Ok(rt) => rt,
+        Err(err) => {
+            log::error!("{}", err);
+            return CheckStatus::ChainError;
+        }
+    };
+
+    let entities = args.entities();
+    let chain_ids: Vec<&str> = networks.iter().map(|network| network.chain_id).collect();
+    let results = run_concurrently(networks, args.concurrency, |network| {
+        check_chain(args, &rt, files, network, &entities)
+    });
+
+    let mut status = CheckStatus::InSync;
+    for (chain_id, result) in chain_ids.into_iter().zip(results) {
+        let chain_status = match result {
+            Ok(Ok(diff)) if diff.is_empty() => {
+                println!("{chain_id}: in sync");
+                CheckStatus::InSync
+            }
+            Ok(Ok(diff)) => {
+                let changes = diff
+                    .summary()
+                    .into_iter()
+                    .map(|(entity, added, removed)| format!("{entity:?} +{added} -{removed}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                println!("{chain_id}: drift, {changes}");
+                CheckStatus::Drift
+            }
+            Ok(Err((chain_status, err))) => {
+                println!("{chain_id}: {chain_status:?}, {err:#}");
+                chain_status
+            }
+            // The scraped data is parsed with panicking helpers in places
+            Err(panic) => {
+                println!("{chain_id}: DataError, {panic}");
+                CheckStatus::DataError
+            }
+        };
+        status = status.max(chain_status);
+    }
+    status
+}
+
+/// Diff of one chain, errors are classified as data or chain errors
+fn check_chain(
+    args: &Arguments,
+    rt: &Runtime,
+    files: &ScrapedFiles,
+    network: ChainInfo,
+    entities: &[EntityType],
+) -> Result<AnsDataDiff, (CheckStatus, anyhow::Error)> {
+    let chain_error =
+        |err: AbstractInterfaceError| (CheckStatus::ChainError, anyhow::Error::from(err));
+    let data_error =
+        |err: AbstractInterfaceError| (CheckStatus::DataError, anyhow::Error::from(err));
+
+    let chain_id = network.chain_id;
+    let ans_host = load_ans_host(rt, network).map_err(|err| (CheckStatus::ChainError, err))?;
+    let scraped_entries =
+        script_helpers::get_scraped_entries(&ans_host, files, entities).map_err(data_error)?;
+    let diagnostics = validation::validate(
+        &ans_host,
+        files,
+        &scraped_entries,
+        entities,
+        args.strict_duplicates,
+    )
+    .map_err(data_error)?;
+    validation::assert_valid(chain_id, &diagnostics).map_err(data_error)?;
+    let on_chain_entries =
+        script_helpers::get_on_chain_entries(&ans_host, entities).map_err(chain_error)?;
+    let diff = script_helpers::diff(scraped_entries, on_chain_entries).map_err(data_error)?;
+    // A node that can't answer is a chain error, only entries it reports missing are data errors
+    let existence = args.existence_diagnostics(&ans_host, &diff).map_err(chain_error)?;
+    validation::assert_valid(chain_id, &existence).map_err(data_error)?;
+    Ok(diff)
+}
+
+/// The diagnostics of the scraped data of a chain and the positions of its scraped entries
+fn validate_chain_data(
+    args: &Arguments,
+    files: &ScrapedFiles,
+    chain_data: &ChainData,
+    entities: &[EntityType],
+) -> anyhow::Result<(Vec<Diagnostic>, EntryPositions)> {
+    let scraped_entries = script_helpers::get_chain_scraped_entries(chain_data, files, entities)?;
+    let diagnostics = validation::validate_data(
+        chain_data,
+        files,
+        &scraped_entries,
+        entities,
+        args.strict_duplicates,
+    )?;
+    let chain_name = config::get().scraped_chain_name(&chain_data.chain_name);
+    let chain_id = chain_data.chain_id.to_string();
+    let positions = report::entry_positions(files, &chain_name, &chain_id)?;
+    Ok((diagnostics, positions))
+}
+
+fn validate_ans(
+    args: &Arguments,
+    files: &ScrapedFiles,
+    report_path: PathBuf,
+) -> anyhow::Result<()> {
+    let entities = args.entities();
+    let mut report = ValidationReport::default();
+    // Only the scraped data is validated, no chain is connected to
+    for network in args.networks()? {
+        let chain_data = config::get().chain_data(network);
+        let chain_name = &config::get().scraped_chain_name(&chain_data.chain_name);
+        let chain_id = &chain_data.chain_id.to_string();
+        match validate_chain_data(args, files, &chain_data, &entities) {
+            Ok((diagnostics, positions)) => {
+                report.add_chain(chain_name, chain_id, diagnostics, &positions)
+            }
+            Err(err) => report.add_chain_error(chain_name, chain_id, format!("{err:#}")),
+        }
+    }
+
+    report.write(&report_path)?;
+    let errors = report.count(Severity::Error);
+    println!(
+        "{} errors and {} warnings, report written to {}",
+        errors,
+        report.count(Severity::Warning),
+        report_path.display()
+    );
+    if errors > 0 {
+        anyhow::bail!("the scraped data has {} validation errors", errors);
+    }
+    Ok(())
+}
+
+fn consistency_ans(out: PathBuf) -> anyhow::Result<()> {
+    let files = ScrapedFiles::fetch(&["assets", "channels"])?;
+    let channels = validation::ibc::parse_channels(files.text("channels")?)?;
+    let mut findings = vec![];
+    for network_type in ["mainnet", "testnet"] {
+        let chain_assets = validation::scraped_chain_assets(&files, network_type)?;
+        findings.extend(consistency::check_consistency(
+            network_type,
+            &chain_assets,
+            &channels,
+        ));
+    }
+
+    // Assets missing on one side are common, only the broken references are worth a line each
+    for finding in &findings {
+        if finding.kind != FindingKind::OnlyOnOrigin {
+            println!("{}", finding);
+        }
+    }
+    for kind in [
+        FindingKind::MissingOnOrigin,
+        FindingKind::TraceMismatch,
+        FindingKind::ChannelUnknown,
+        FindingKind::OnlyOnOrigin,
+    ] {
+        let count = findings.iter().filter(|finding| finding.kind == kind).count();
+        println!("{:?}: {}", kind, count);
+    }
+    std::fs::write(&out, serde_json::to_string_pretty(&findings)?)?;
+    println!("Report written to {}", out.display());
+    Ok(())
+}
+
+fn plan_ans(args: &Arguments, files: &ScrapedFiles, out: PathBuf) -> anyhow::Result<()> {
+    let rt = Runtime::new()?;
+    let entities = args.entities();
+    let mut chain_plans: Vec<ChainPlan> = vec![];
+    for network in args.networks()? {
+        let chain_id = network.chain_id;
+        let ans_host = load_ans_host(&rt, network)?;
+        let scraped_entries = script_helpers::get_scraped_entries(&ans_host, files, &entities)?;
+        let diagnostics = validation::validate(
+            &ans_host,
+            files,
+            &scraped_entries,
+            &entities,
+            args.strict_duplicates,
+        )?;
+        validation::assert_valid(chain_id, &diagnostics)?;
+        let on_chain_entries = script_helpers::get_on_chain_entries(&ans_host, &entities)?;
+        let diff = script_helpers::diff(scraped_entries.clone(), on_chain_entries.clone())?;
+        args.check_removals(chain_id, &diff, &on_chain_entries)?;
+        args.check_existence(chain_id, &ans_host, &diff)?;
+
+        let chain_plan =
+            plan::plan_chain(&ans_host, scraped_entries, on_chain_entries, &entities)?;
+        println!("{}", chain_plan);
+        chain_plans.push(chain_plan);
+    }
+
+    let plan = Plan::new(chain_plans)?;
+    plan.write(&out)?;
+    println!("Plan {} written to {}", plan.hash, out.display());
+    Ok(())
+}
+
+fn apply_ans(args: &Arguments, plan_path: PathBuf) -> anyhow::Result<()> {
+    let rt = Runtime::new()?;
+    let plan = Plan::read(&plan_path)?;
+    let journal = Journal::new(&args.journal_dir)?;
+    log::info!(
+        "Applying plan {}, recording run journal at {}",
+        plan.hash,
+        journal.path().display()
+    );
+    let runs = plan
+        .chains
+        .iter()
+        .map(|chain_plan| Ok((network(&chain_plan.chain_id)?, chain_plan.msgs.len())))
+        .collect::<anyhow::Result<_>>()?;
+    let unfunded = preflight_balances(args, &rt, runs)?;
+    // Every grant is checked before the first chain is applied
+    for chain_plan in &plan.chains {
+        if unfunded.contains(&chain_plan.chain_id) {
+            continue;
+        }
+        if let Execution::Authz { granter } = &args.execution(&chain_plan.chain_id) {
+            let ans_host = load_ans_host(&rt, network(&chain_plan.chain_id)?)?;
+            let calls = chain_plan.msgs.len();
+            script_helpers::execution::assert_authz_grant(&ans_host, granter, calls)?;
+        }
+    }
+    // Every verification compares with the same scraped files
+    let files = if args.skip_verify {
+        ScrapedFiles::default()
+    } else {
+        ScrapedFiles::fetch(&sections::scraped_files(ANS_DATA_ENTITIES))?
+    };
+
+    for chain_plan in plan.chains {
+        if unfunded.contains(&chain_plan.chain_id) {
+            continue;
+        }
+        let network = network(&chain_plan.chain_id)?;
+        let chain_id = network.chain_id;
+        let execution = args.execution(chain_id);
+
+        let ans_host = load_ans_host(&rt, network)?;
+        let entities = chain_plan.entities.clone();
+        let on_chain_entries = script_helpers::get_on_chain_entries(&ans_host, &entities)?;
+        plan::assert_on_chain_unchanged(&chain_plan, &on_chain_entries)?;
+        // The limits of this run apply, not the ones the plan was made with
+        args.check_removals(chain_id, &AnsDataDiff::from(&chain_plan.diff), &on_chain_entries)?;
+        Snapshot::new(chain_id, &on_chain_entries, &entities)
+            .write(journal.snapshot_path(chain_id))?;
+
+        log::info!("{}", chain_plan);
+        script_helpers::execution::execute(
+            &ans_host,
+            chain_plan.msgs,
+            &execution,
+            Some(&journal),
+        )?;
+
+        if args.skip_verify || matches!(execution, Execution::Proposal(_)) {
+            continue;
+        }
+        // We can only compare against the scraped data when it's still the data the plan was made from
+        let scraped_entries = script_helpers::get_scraped_entries(&ans_host, &files, &entities)?;
+        if plan::entries_hash(chain_id, &scraped_entries, &entities)? != chain_plan.scraped_hash {
+            log::warn!(
+                "Scraped data for {} changed since the plan was made, skipping verification",
+                chain_id
+            );
+            continue;
+        }
+        let report = script_helpers::verify::verify(&ans_host, scraped_entries, &entities)?;
+        if !report.is_in_sync() {
+            anyhow::bail!(
+                "ANS host on {} is still out of sync after applying the plan:\n{}",
+                chain_id,
+                report
+            );
+        }
+        log::info!("{}", report);
+    }
+    Ok(())
+}
+
+fn rollback_ans(
+    args: &Arguments,
+    journal_path: Option<PathBuf>,
+    snapshot: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let rt = Runtime::new()?;
+    let journal = Journal::new(&args.journal_dir)?;
+    log::info!("Recording rollback journal at {}", journal.path().display());
+
+    // The state to restore per chain, limited to the entries the run touched when we undo a journal
+    let targets: Vec<(Snapshot, Option<TouchedKeys>)> = match (journal_path, snapshot) {
+        (Some(journal_path), _) => {
+            let records = Journal::read(&journal_path)?;
+            let chain_ids: BTreeSet<&str> =
+                records.iter().map(|r| r.chain_id.as_str()).collect();
+            chain_ids
+                .into_iter()
+                .map(|chain_id| {
+                    let (added, removed) =
+                        rollback::count_records(rollback::chain_records(&records, chain_id));
+                    log::info!(
+                        "Undoing {} added or updated and {} removed entries on {}",
+                        added,
+                        removed,
+                        chain_id
+                    );
+                    let snapshot = Snapshot::read(snapshot_path(&journal_path, chain_id))?;
+                    let touched =
+                        TouchedKeys::from_records(rollback::chain_records(&records, chain_id));
+                    Ok((snapshot, Some(touched)))
+                })
+                .collect::<anyhow::Result<_>>()?
+        }
+        (None, Some(snapshot)) => vec![(Snapshot::read(snapshot)?, None)],
+        (None, None) => anyhow::bail!("a journal or a snapshot is required to roll back"),
+    };
+
+    for (snapshot, touched) in targets {
+        let network = network(&snapshot.chain_id)?;
+        if !args.kind_selected(&network.kind) {
+            continue;
+        }
+        let chain_id = network.chain_id;
+        let execution = args.execution(chain_id);
+
+        let ans_host = load_ans_host(&rt, network)?;
+        // Only the entity types in the snapshot can be restored
+        let entities = snapshot.entities.clone();
+        let target_entries = snapshot.into_ans_data();
+        let on_chain_entries = script_helpers::get_on_chain_entries(&ans_host, &entities)?;
+        Snapshot::new(chain_id, &on_chain_entries, &entities)
+            .write(journal.snapshot_path(chain_id))?;
+
+        let diff = rollback::rollback_diff(
+            target_entries.clone(),
+            on_chain_entries.clone(),
+            touched.as_ref(),
+        )?;
+        args.check_removals(chain_id, &diff, &on_chain_entries)?;
+        let msgs = script_helpers::update_msgs(diff);
+        if let Execution::Authz { granter } = &execution {
+            script_helpers::execution::assert_authz_grant(&ans_host, granter, msgs.len())?;
+        }
+        script_helpers::execution::execute(&ans_host, msgs, &execution, Some(&journal))?;
+
+        if args.skip_verify || matches!(execution, Execution::Proposal(_)) {
+            continue;
+        }
+        let on_chain_entries = script_helpers::get_on_chain_entries(&ans_host, &entities)?;
+        let residual = rollback::rollback_diff(target_entries, on_chain_entries, touched.as_ref())?;
+        if !residual.is_empty() {
+            let report = VerificationReport {
+                residual,
+                ..Default::default()
+            };
+            anyhow::bail!(
+                "ANS host on {} is not fully rolled back:\n{}",
+                chain_id,
+                report
+            );
+        }
+        log::info!("ANS host on {} rolled back", chain_id);
+    }
+    Ok(())
+}
+
+fn init_config(args: &Arguments) -> anyhow::Result<()> {
+    config::set(args.config()?)?;
+    Ok(())
+}
+
+/// The selected chains the scraped data doesn't resolve to, logged per chain.
+/// Errors out when none of them resolves.
+fn check_scraped_sections(args: &Arguments, files: &ScrapedFiles) -> anyhow::Result<Vec<String>> {
+    let config = config::get();
+    let selected: Vec<(String, String)> = args
+        .networks()?
+        .into_iter()
+        .map(|network| {
+            let chain_data = config.chain_data(network);
+            (
+                config.scraped_chain_name(&chain_data.chain_name),
+                chain_data.chain_id.to_string(),
+            )
+        })
+        .collect();
+
+    let report = sections::check_scraped_sections(files, &selected, &args.entities())?;
+    for section in &report.unknown {
+        log::warn!("scraped section {} is not a known chain", section);
+    }
+    if !report.unused.is_empty() {
+        log::info!(
+            "{} scraped sections are not used by the selected chains: {}",
+            report.unused.len(),
+            report.unused.join(", ")
+        );
+    }
+    let mut unresolved: Vec<String> = vec![];
+    for (chain_id, error) in report.errors {
+        log::error!("{}: {}", chain_id, error);
+        if !unresolved.contains(&chain_id) {
+            unresolved.push(chain_id);
+        }
+    }
+    if !selected.is_empty() && unresolved.len() == selected.len() {
+        anyhow::bail!("none of the selected chains resolves to the scraped data");
+    }
+    Ok(unresolved)
+}
+
+/// The scraped files a run over `entities` reads, the IBC assets are checked against the channels
+fn scraped_run_files(entities: &[EntityType]) -> Vec<&'static str> {
+    let mut files = sections::scraped_files(entities);
+    if entities.contains(&EntityType::Assets) {
+        files.push("channels");
+    }
+    files
+}
+
+/// Channels aren't diffed, only the commands that send every channel of the channels file sync them
+fn check_channels_selection(args: &Arguments) -> anyhow::Result<()> {
+    let syncs_channels = matches!(
+        args.command,
+        None | Some(Command::Update | Command::Bootstrap { .. })
+    );
+    if args.only.contains(&EntityType::Channels) && !syncs_channels {
+        anyhow::bail!("channels are only synced by `update` and `bootstrap`, drop them from --only");
+    }
+    Ok(())
+}
+
+/// Outcome of a `check` that stopped before any chain was diffed. Scraped files that can't be
+/// downloaded say nothing about the data, like an unreachable node. A broken config or scraped
+/// data no chain resolves to is a data error.
+fn startup_status(err: &anyhow::Error) -> CheckStatus {
+    if err.is::<FetchError>() {
+        CheckStatus::ChainError
+    } else {
+        CheckStatus::DataError
+    }
+}
+
+fn run(args: &mut Arguments) -> anyhow::Result<()> {
+    let uses_scraped_data = matches!(
+        args.command,
+        None | Some(
+            Command::Update
+                | Command::Check
+                | Command::Validate { .. }
+                | Command::Plan { .. }
+                | Command::Bootstrap { .. }
+        )
+    );
+    let startup = init_config(args)
+        .and_then(|()| check_channels_selection(args))
+        .and_then(|()| {
+            if !uses_scraped_data {
+                return Ok((ScrapedFiles::default(), vec![]));
+            }
+            // Every chain of the run reads the same files, they're fetched once
+            let files = ScrapedFiles::fetch(&scraped_run_files(&args.entities()))?;
+            let unresolved = check_scraped_sections(args, &files)?;
+            Ok((files, unresolved))
+        });
+    let files = match startup {
+        Ok((files, unresolved)) => {
+            args.unresolved_chains = unresolved;
+            files
+        }
+        Err(err) => {
+            if matches!(args.command, Some(Command::Check)) {
+                log::error!("{:#}", err);
+                ::std::process::exit(startup_status(&err) as i32);
+            }
+            return Err(err);
+        }
+    };
+    let args = &*args;
+    let files = &files;
+
+    // The chains the scraped data resolves to are run, the others still fail the run
+    let unresolved = args.unresolved_chains.join(", ");
+    let result = match args.command.clone().unwrap_or(Command::Update) {
+        Command::Update => update_ans(args, files),
+        Command::Check => {
+            let mut status = check_ans(args, files);
+            for chain_id in &args.unresolved_chains {
+                println!("{chain_id}: DataError, the scraped data doesn't resolve to it");
+                status = status.max(CheckStatus::DataError);
+            }
+            ::std::process::exit(status as i32);
+        }
+        Command::Validate { report } => validate_ans(args, files, report),
+        Command::Consistency { out } => consistency_ans(out),
+        Command::Plan { out } => plan_ans(args, files, out),
+        Command::Bootstrap { ans_host, state } => bootstrap_ans(args, files, ans_host, state),
+        Command::Apply { plan } => apply_ans(args, plan),
+        Command::Rollback { journal, snapshot } => rollback_ans(args, journal, snapshot),
+    };
+    if result.is_ok() && !unresolved.is_empty() {
+        anyhow::bail!("left out {unresolved}, the scraped data doesn't resolve to them");
+    }
+    result
+}
+
 fn main() {
     dotenv().ok();
     env_logger::init();
 
     use dotenv::dotenv;
 
-    if let Err(ref err) = update_ans() {
+    let mut args = Arguments::parse();
+
+    let result = run(&mut args);
+
+    if let Err(ref err) = result {
         log::error!("{}", err);
         err.chain()
             .skip(1)
@@ -80,3 +1207,31 @@ fn main() {
         ::std::process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fetch_failures_are_chain_errors() {
+        let unreachable = FetchError("failed to fetch https://example.com/assets.json".into());
+        assert_eq!(startup_status(&unreachable.into()), CheckStatus::ChainError);
+
+        let invalid: AbstractInterfaceError =
+            CwOrchError::StdErr("invalid json in assets.json".into()).into();
+        assert_eq!(startup_status(&invalid.into()), CheckStatus::DataError);
+        let unresolved = anyhow::anyhow!("none of the selected chains resolves");
+        assert_eq!(startup_status(&unresolved), CheckStatus::DataError);
+    }
+
+    #[test]
+    fn overrides_batch_sizes() {
+        let args = Arguments::parse_from(["update_ans", "--batch-size-pools", "5"]);
+
+        let batch_sizes = args.config().unwrap().batch_sizes;
+
+        assert_eq!(batch_sizes.pools.get(), 5);
+        assert_eq!(batch_sizes.assets, Config::default().batch_sizes.assets);
+        assert!(Arguments::try_parse_from(["update_ans", "--batch-size-pools", "0"]).is_err());
+    }
+}
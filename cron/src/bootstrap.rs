@@ -0,0 +1,232 @@
+//! Upload of the scraped data to a freshly deployed ANS host, in batches as large as a transaction allows
+
+use std::fmt::Display;
+
+use abstract_core::ans_host::ExecuteMsg;
+use abstract_interface::{AbstractInterfaceError, AnsHost};
+use cw_orch::prelude::*;
+
+use crate::execution::{self, Execution};
+use crate::journal::Journal;
+use crate::{AnsDataDiff, EntityType};
+
+/// Parts of the errors of transactions that are too large or need more gas than a transaction can have
+const BATCH_TOO_LARGE_ERRORS: &[&str] = &[
+    "out of gas",
+    "tx too large",
+    "exceeds block gas limit",
+    "exceeds max gas",
+    "greater than max gas",
+    "larger than max",
+];
+
+/// Largest batch a bootstrap tries, batches start at the configured batch size and grow up to it
+pub const MAX_BATCH_SIZE: usize = 250;
+
+/// Whether a smaller batch can avoid `error`
+pub fn is_batch_too_large(error: &str) -> bool {
+    let error = error.to_lowercase();
+    BATCH_TOO_LARGE_ERRORS
+        .iter()
+        .any(|pattern| error.contains(pattern))
+}
+
+/// Send `items` in consecutive chunks, starting at `batch_size` entries. Every chunk that goes through
+/// grows the next one towards `max_batch_size`. A chunk that is too large or runs out of gas is split until
+/// it goes through, after which the chunk size grows back towards the smallest size that failed.
+/// Any other error is returned right away. Returns the sizes of the chunks that were sent.
+pub fn send_in_largest_batches<T, E: Display>(
+    items: &[T],
+    batch_size: usize,
+    max_batch_size: usize,
+    mut send: impl FnMut(&[T]) -> Result<(), E>,
+) -> Result<Vec<usize>, E> {
+    let mut sent = vec![];
+    let mut offset = 0;
+    let mut size = batch_size.max(1);
+    // Largest chunk that went through and smallest chunk that didn't
+    let mut fits = 0;
+    let mut too_large = max_batch_size.max(size) + 1;
+    while offset < items.len() {
+        let end = items.len().min(offset + size);
+        match send(&items[offset..end]) {
+            Ok(()) => {
+                sent.push(end - offset);
+                fits = fits.max(end - offset);
+                offset = end;
+                size = size.max((size + too_large) / 2);
+            }
+            Err(err) if size > 1 && is_batch_too_large(&err.to_string()) => {
+                too_large = size;
+                fits = fits.min(size - 1);
+                size = fits.max(size / 2);
+                log::warn!("batch of {too_large} entries failed, retrying with {size}: {err}");
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(sent)
+}
+
+fn upload<T>(
+    ans_host: &AnsHost<Daemon>,
+    entity: EntityType,
+    items: &[T],
+    batch_size: usize,
+    msg_builder: impl Fn(&[T]) -> ExecuteMsg,
+    execution: &Execution,
+    journal: Option<&Journal>,
+) -> Result<(), AbstractInterfaceError> {
+    if items.is_empty() {
+        return Ok(());
+    }
+    let sent = send_in_largest_batches(items, batch_size, MAX_BATCH_SIZE, |chunk| {
+        execution::execute(ans_host, vec![msg_builder(chunk)], execution, journal)
+    })?;
+    log::info!(
+        "Uploaded {} {:?} in {} transactions of up to {} entries",
+        items.len(),
+        entity,
+        sent.len(),
+        sent.iter().max().unwrap_or(&0)
+    );
+    Ok(())
+}
+
+/// Transactions a bootstrap of `diff` sends when every batch of the configured size fits.
+/// Batches that have to be split take more.
+pub fn transaction_count(diff: &AnsDataDiff) -> usize {
+    let batch_sizes = crate::config::get().batch_sizes;
+    [
+        (diff.contracts.1.len(), batch_sizes.contracts),
+        (diff.assets.1.len(), batch_sizes.assets),
+        (diff.dexes.1.len(), batch_sizes.dexes),
+        (diff.pools.1.len(), batch_sizes.pools),
+    ]
+    .into_iter()
+    .map(|(entries, batch_size)| entries.div_ceil(batch_size.get()))
+    .sum()
+}
+
+/// Add the entries `diff` adds or updates, in the order the ANS host needs them: pools reference assets
+/// and dexes. The removals of `diff` are left out, a bootstrap only adds.
+pub fn bootstrap(
+    ans_host: &AnsHost<Daemon>,
+    diff: AnsDataDiff,
+    execution: &Execution,
+    journal: Option<&Journal>,
+) -> Result<(), AbstractInterfaceError> {
+    let batch_sizes = crate::config::get().batch_sizes;
+    let contracts: Vec<_> = diff.contracts.1.into_iter().collect();
+    upload(
+        ans_host,
+        EntityType::Contracts,
+        &contracts,
+        batch_sizes.contracts.get(),
+        |chunk| ExecuteMsg::UpdateContractAddresses {
+            to_add: chunk.to_vec(),
+            to_remove: vec![],
+        },
+        execution,
+        journal,
+    )?;
+
+    let assets: Vec<_> = diff.assets.1.into_iter().collect();
+    upload(
+        ans_host,
+        EntityType::Assets,
+        &assets,
+        batch_sizes.assets.get(),
+        |chunk| ExecuteMsg::UpdateAssetAddresses {
+            to_add: chunk.to_vec(),
+            to_remove: vec![],
+        },
+        execution,
+        journal,
+    )?;
+
+    let dexes: Vec<_> = diff.dexes.1.into_keys().collect();
+    upload(
+        ans_host,
+        EntityType::Dexes,
+        &dexes,
+        batch_sizes.dexes.get(),
+        |chunk| ExecuteMsg::UpdateDexes {
+            to_add: chunk.to_vec(),
+            to_remove: vec![],
+        },
+        execution,
+        journal,
+    )?;
+
+    let pools: Vec<_> = diff.pools.1.into_iter().collect();
+    upload(
+        ans_host,
+        EntityType::Pools,
+        &pools,
+        batch_sizes.pools.get(),
+        |chunk| ExecuteMsg::UpdatePools {
+            to_add: chunk.to_vec(),
+            to_remove: vec![],
+        },
+        execution,
+        journal,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn converges_on_the_largest_batch_that_fits() {
+        let items: Vec<u32> = (0..12).collect();
+        let mut attempts = vec![];
+
+        let sent = send_in_largest_batches(&items, 10, 10, |chunk| {
+            attempts.push(chunk.len());
+            if chunk.len() > 3 {
+                Err("out of gas in location: WriteFlat; gasWanted: 2000000")
+            } else {
+                Ok(())
+            }
+        })
+        .unwrap();
+
+        assert_eq!(attempts, vec![10, 5, 2, 3, 4, 3, 3, 1]);
+        assert_eq!(sent, vec![2, 3, 3, 3, 1]);
+    }
+
+    #[test]
+    fn grows_past_the_configured_size() {
+        let items: Vec<u32> = (0..100).collect();
+
+        let sent = send_in_largest_batches(&items, 10, 40, |_| Ok::<_, &str>(())).unwrap();
+
+        assert_eq!(sent, vec![10, 25, 33, 32]);
+    }
+
+    #[test]
+    fn returns_other_errors_right_away() {
+        let items = vec!["a", "b", "c"];
+        let mut attempts = 0;
+
+        let result = send_in_largest_batches(&items, 25, MAX_BATCH_SIZE, |_| {
+            attempts += 1;
+            Err("unauthorized: sender is not the admin")
+        });
+
+        assert_eq!(result, Err("unauthorized: sender is not the admin"));
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn recognizes_batches_that_are_too_large() {
+        assert!(is_batch_too_large(
+            "Tx too large. Max size is 1048576, but got 1300000"
+        ));
+        assert!(is_batch_too_large("out of gas in location: ReadFlat"));
+        assert!(!is_batch_too_large("insufficient funds"));
+        assert!(!is_batch_too_large("transport error"));
+    }
+}
@@ -1,41 +1,128 @@
-use std::fs::File;
-
 use abstract_core::ans_host::*;
 use abstract_core::objects::UncheckedChannelEntry;
 use abstract_interface::{AbstractInterfaceError, AnsHost};
 use cw_orch::prelude::*;
 use cw_orch::state::ChainState;
-use serde_json::from_reader;
 
 use crate::batch_execute_ans;
+use crate::execution::Execution;
+use crate::journal::Journal;
+use crate::validation::ibc::{parse_channels, Channels};
+
+/// Protocol of the channels in the channels file
+const ICS20: &str = "ics20";
+
+fn ics20_entry(connected_chain: &str) -> UncheckedChannelEntry {
+    UncheckedChannelEntry {
+        connected_chain: connected_chain.to_string(),
+        protocol: ICS20.to_string(),
+    }
+}
 
-const PATH: &str = "resources/old/channels.json";
-
-pub fn update_channels(ans: &AnsHost<Daemon>) -> Result<(), AbstractInterfaceError> {
-    let file = File::open(PATH).unwrap_or_else(|_| panic!("file should be present at {}", PATH));
-    let json: serde_json::Value = from_reader(file)?;
-    let chain_name = &ans.get_chain().state().chain_data.chain_name;
-    let chain_id = ans.get_chain().state().chain_data.chain_id.to_string();
-    let channels = json
-        .get(chain_name)
-        .unwrap()
-        .get(chain_id)
-        .ok_or_else(|| CwOrchError::StdErr("network not found".into()))?;
-
-    let channels = channels.as_object().unwrap();
-    let channels_to_add: Vec<(UncheckedChannelEntry, String)> = channels
-        .iter()
-        .map(|(name, value)| {
-            let id = value.as_str().unwrap().to_owned();
-            let key = UncheckedChannelEntry::try_from(name.clone()).unwrap();
-            (key, id)
-        })
-        .collect();
-
-    batch_execute_ans(&ans, &channels_to_add, 25, |chunk| ExecuteMsg::UpdateChannels {
-        to_add: chunk.to_vec(),
-        to_remove: vec![],
+/// Read the channels file, `{chain: {counterparty: [channel ids]}}` like the scraped `channels.json`
+pub fn read_channels(path: &str) -> Result<Channels, AbstractInterfaceError> {
+    let text = std::fs::read_to_string(path).map_err(|e| {
+        CwOrchError::StdErr(format!("failed to read the channels file {path}: {e}"))
     })?;
+    parse_channels(&text)
+}
+
+/// The ICS20 channel entries of `chain_name`, sorted by counterparty.
+/// A counterparty with several channels can't be registered, it's logged and left out.
+pub fn channel_entries(
+    channels: &Channels,
+    chain_name: &str,
+) -> Vec<(UncheckedChannelEntry, String)> {
+    let Some(counterparties) = channels.get(chain_name) else {
+        return vec![];
+    };
+    let mut entries = vec![];
+    for (counterparty, channel_ids) in counterparties {
+        match channel_ids.as_slice() {
+            [channel_id] => entries.push((ics20_entry(counterparty), channel_id.clone())),
+            _ => log::warn!(
+                "{} has {} channels to {}, none is registered: {}",
+                chain_name,
+                channel_ids.len(),
+                counterparty,
+                channel_ids.join(", ")
+            ),
+        }
+    }
+    entries.sort_by(|(a, _), (b, _)| a.connected_chain.cmp(&b.connected_chain));
+    entries
+}
+
+pub fn update_channels(
+    ans: &AnsHost<Daemon>,
+    execution: &Execution,
+    journal: Option<&Journal>,
+) -> Result<(), AbstractInterfaceError> {
+    let config = crate::config::get();
+    let chain_data = &ans.get_chain().state().chain_data;
+    // The channels file is keyed by chain name, which mainnets and testnets share
+    if chain_data.network_type != "mainnet" {
+        return Err(CwOrchError::StdErr(format!(
+            "the channels file only lists mainnet channels, {} is a {}",
+            chain_data.chain_id, chain_data.network_type
+        ))
+        .into());
+    }
+    let chain_name = config.scraped_chain_name(&chain_data.chain_name);
+    let channels = read_channels(&config.channels_path)?;
+    if !channels.contains_key(&chain_name) {
+        return Err(CwOrchError::StdErr(format!(
+            "no channels of {} in {}",
+            chain_name, config.channels_path
+        ))
+        .into());
+    }
+    let channels_to_add = channel_entries(&channels, &chain_name);
+
+    batch_execute_ans(
+        ans,
+        &channels_to_add,
+        config.batch_sizes.channels.get(),
+        |chunk| ExecuteMsg::UpdateChannels {
+            to_add: chunk.to_vec(),
+            to_remove: vec![],
+        },
+        execution,
+        journal,
+    )?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn registers_counterparties_with_one_channel() {
+        let channels = Channels::from([(
+            "osmosis".to_string(),
+            HashMap::from([
+                ("juno".to_string(), vec!["channel-42".to_string()]),
+                ("archway".to_string(), vec!["channel-1429".to_string()]),
+                (
+                    "cosmoshub".to_string(),
+                    vec!["channel-0".to_string(), "channel-6".to_string()],
+                ),
+            ]),
+        )]);
+
+        let entries = channel_entries(&channels, "osmosis");
+
+        assert_eq!(
+            entries,
+            vec![
+                (ics20_entry("archway"), "channel-1429".to_string()),
+                (ics20_entry("juno"), "channel-42".to_string()),
+            ]
+        );
+        assert!(channel_entries(&channels, "juno").is_empty());
+    }
+}
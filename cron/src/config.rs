@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use abstract_core::objects::PoolType;
+use abstract_interface::AbstractInterfaceError;
+use cw_orch::daemon::networks::NetworkInfo;
+use cw_orch::daemon::{ChainInfo, ChainKind, ChainRegistryData as ChainData};
+use cw_orch::prelude::*;
+use serde::Deserialize;
+
+/// The configuration the updater ships with
+const DEFAULT_CONFIG: &str = include_str!("../updater.toml");
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Base URL or local directory of the scraped ANS data
+    pub scrape_url: String,
+    /// Channels file used when syncing channels, in the format of the scraped `channels.json`
+    pub channels_path: String,
+    /// Gas the balance preflight reserves for every transaction a run broadcasts
+    pub gas_per_transaction: u64,
+    /// Chains an asset name can be prefixed with
+    pub known_chains: Vec<String>,
+    pub batch_sizes: BatchSizes,
+    /// Names of chains in the scraped data, by cw-orch chain name, for chains the scraper names differently
+    #[serde(default)]
+    pub chain_aliases: HashMap<String, String>,
+    /// Pools a dex can have, by dex name
+    #[serde(default)]
+    pub dexes: HashMap<String, DexRules>,
+    /// Chains that cw-orch doesn't define and overrides of the cw-orch network definitions, by chain id
+    #[serde(default)]
+    pub chains: HashMap<String, ChainConfig>,
+}
+
+/// Number of entries sent in a single ANS host message, per entity type. A batch can't be empty.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchSizes {
+    pub contracts: NonZeroUsize,
+    pub assets: NonZeroUsize,
+    pub dexes: NonZeroUsize,
+    pub pools: NonZeroUsize,
+    pub channels: NonZeroUsize,
+}
+
+/// How the pools of a dex are addressed, which pool types it has and what its LP tokens are
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DexRules {
+    pub addresses: Vec<PoolAddressKind>,
+    pub pool_types: Vec<PoolType>,
+    /// Denom of the LP token of a pool addressed by id, with `{pool_id}` in place of the id.
+    /// Without it no LP asset is registered for those pools.
+    pub lp_denom: Option<String>,
+}
+
+impl DexRules {
+    /// The LP token denom of the pool with id `pool_id`
+    pub fn lp_denom(&self, pool_id: u64) -> Option<String> {
+        let template = self.lp_denom.as_ref()?;
+        Some(template.replace("{pool_id}", &pool_id.to_string()))
+    }
+}
+
+/// Variant of a pool address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolAddressKind {
+    Id,
+    Contract,
+    SeparateAddresses,
+}
+
+/// A chain section. With a `chain_name` it defines a chain and needs every key but `coin_type`,
+/// without it overrides the values it sets on the cw-orch network of the same id.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainConfig {
+    /// Name of the chain in cw-orch and in the scraped data
+    pub chain_name: Option<String>,
+    pub kind: Option<NetworkKind>,
+    pub gas_denom: Option<String>,
+    pub gas_price: Option<f64>,
+    pub grpc_urls: Option<Vec<String>>,
+    pub bech32_prefix: Option<String>,
+    /// Defaults to 118
+    pub coin_type: Option<u32>,
+    /// `grpc_urls` as borrowed by the network of a defined chain
+    #[serde(skip)]
+    grpc_url_refs: OnceLock<Vec<&'static str>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkKind {
+    Mainnet,
+    Testnet,
+    Local,
+}
+
+impl From<NetworkKind> for ChainKind {
+    fn from(kind: NetworkKind) -> Self {
+        match kind {
+            NetworkKind::Mainnet => ChainKind::Mainnet,
+            NetworkKind::Testnet => ChainKind::Testnet,
+            NetworkKind::Local => ChainKind::Local,
+        }
+    }
+}
+
+impl ChainConfig {
+    fn network(
+        &'static self,
+        chain_id: &'static str,
+    ) -> Result<ChainInfo<'static>, AbstractInterfaceError> {
+        let missing =
+            |key: &str| CwOrchError::StdErr(format!("chain {chain_id} is defined without `{key}`"));
+        let grpc_urls = self
+            .grpc_urls
+            .as_ref()
+            .ok_or_else(|| missing("grpc_urls"))?;
+        Ok(ChainInfo {
+            kind: self.kind.ok_or_else(|| missing("kind"))?.into(),
+            chain_id,
+            gas_denom: self
+                .gas_denom
+                .as_deref()
+                .ok_or_else(|| missing("gas_denom"))?,
+            gas_price: self.gas_price.ok_or_else(|| missing("gas_price"))?,
+            grpc_urls: self
+                .grpc_url_refs
+                .get_or_init(|| grpc_urls.iter().map(String::as_str).collect()),
+            network_info: NetworkInfo {
+                id: self
+                    .chain_name
+                    .as_deref()
+                    .ok_or_else(|| missing("chain_name"))?,
+                pub_address_prefix: self
+                    .bech32_prefix
+                    .as_deref()
+                    .ok_or_else(|| missing("bech32_prefix"))?,
+                coin_type: self.coin_type.unwrap_or(118),
+            },
+            lcd_url: None,
+            fcd_url: None,
+        })
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        toml::from_str(DEFAULT_CONFIG).expect("default updater config is valid")
+    }
+}
+
+impl Config {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AbstractInterfaceError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            CwOrchError::StdErr(format!("config not found at {}: {e}", path.display()))
+        })?;
+        toml::from_str(&content).map_err(|e| {
+            CwOrchError::StdErr(format!("invalid config {}: {e}", path.display())).into()
+        })
+    }
+
+    /// The network of `chain_id`: its definition in the config, or else the cw-orch network of that id
+    pub fn network(
+        &'static self,
+        chain_id: &str,
+    ) -> Result<ChainInfo<'static>, AbstractInterfaceError> {
+        match self.chains.get_key_value(chain_id) {
+            Some((chain_id, chain_config)) if chain_config.chain_name.is_some() => {
+                chain_config.network(chain_id)
+            }
+            _ => networks::parse_network(chain_id)
+                .map_err(|e| CwOrchError::StdErr(format!("unknown chain {chain_id}: {e}")).into()),
+        }
+    }
+
+    /// The name of the chain sections of `chain_name` in the scraped data
+    pub fn scraped_chain_name(&self, chain_name: &str) -> String {
+        self.chain_aliases
+            .get(chain_name)
+            .cloned()
+            .unwrap_or_else(|| chain_name.to_string())
+    }
+
+    /// The chain data of `network` with the overrides of its chain section applied
+    pub fn chain_data(&self, network: ChainInfo) -> ChainData {
+        let chain_id = network.chain_id;
+        let mut chain_data: ChainData = network.into();
+        let Some(chain_config) = self.chains.get(chain_id) else {
+            return chain_data;
+        };
+
+        if let Some(fee_token) = chain_data.fees.fee_tokens.first_mut() {
+            if let Some(gas_denom) = &chain_config.gas_denom {
+                fee_token.denom = gas_denom.clone();
+            }
+            if let Some(gas_price) = chain_config.gas_price {
+                fee_token.fixed_min_gas_price = gas_price;
+            }
+        }
+        if let Some(grpc_urls) = &chain_config.grpc_urls {
+            if let Some(template) = chain_data.apis.grpc.first().cloned() {
+                chain_data.apis.grpc = grpc_urls
+                    .iter()
+                    .map(|url| {
+                        let mut grpc = template.clone();
+                        grpc.address = url.clone();
+                        grpc
+                    })
+                    .collect();
+            }
+        }
+        chain_data
+    }
+}
+
+/// Set the configuration for the rest of the process, can only be done once and before [`get`] is called
+pub fn set(config: Config) -> Result<(), AbstractInterfaceError> {
+    CONFIG
+        .set(config)
+        .map_err(|_| CwOrchError::StdErr("updater config is already set".into()).into())
+}
+
+/// The configuration set with [`set`], or the default configuration
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default_config_parses() {
+        let config = Config::default();
+
+        assert_eq!(config.batch_sizes.contracts.get(), 10);
+        assert_eq!(config.batch_sizes.pools.get(), 25);
+        assert_eq!(config.gas_per_transaction, 2_000_000);
+        assert_eq!(config.chains["neutron-1"].gas_price, Some(0.25));
+        assert_eq!(config.dexes["osmosis"].addresses, vec![PoolAddressKind::Id]);
+        assert_eq!(
+            config.dexes["osmosis"].lp_denom(1),
+            Some("gamm/pool/1".to_string())
+        );
+        assert_eq!(config.dexes["astroport"].lp_denom(1), None);
+    }
+
+    #[test]
+    fn rejects_empty_batches() {
+        let config = DEFAULT_CONFIG.replace("\npools = 25", "\npools = 0");
+
+        assert!(toml::from_str::<Config>(&config).is_err());
+    }
+
+    #[test]
+    fn defines_chains_from_the_config() {
+        // Tests never set a config, this is the bundled one
+        let config = get();
+
+        let network = config.network("archway-1").unwrap();
+
+        assert_eq!(network.network_info.id, "archway");
+        assert_eq!(network.network_info.pub_address_prefix, "archway");
+        assert_eq!(network.grpc_urls.len(), 1);
+        assert!(matches!(network.kind, ChainKind::Mainnet));
+        assert!(config.network("unknown-1").is_err());
+    }
+}
@@ -6,13 +6,14 @@ use abstract_interface::{AbstractInterfaceError, AnsHost};
 use cw_orch::prelude::*;
 use serde_json::Value;
 
-use crate::{batch_execute_ans, EntryDif};
+use crate::{batch_ans_msgs, EntryDif, ScrapedFiles};
 
 pub fn get_scraped_entries(
+    files: &ScrapedFiles,
     chain_name: &String,
     chain_id: &String,
 ) -> Result<HashMap<UncheckedContractEntry, String>, AbstractInterfaceError> {
-    let raw_scraped_entries = crate::get_scraped_json_data("contracts");
+    let raw_scraped_entries = files.json("contracts")?;
 
     let binding = raw_scraped_entries[chain_name][chain_id].clone();
     let bd = vec![];
@@ -20,12 +21,8 @@ pub fn get_scraped_entries(
 
     let scraped_entries_vec: Vec<(UncheckedContractEntry, String)> = parsed_scraped_entries
         .iter()
-        .map(|value| {
-            let contract: (UncheckedContractEntry, String) =
-                serde_json::from_value(value.clone()).unwrap();
-            contract
-        })
-        .collect();
+        .map(|value| serde_json::from_value(value.clone()))
+        .collect::<Result<_, _>>()?;
 
     Ok(scraped_entries_vec.into_iter().collect())
 }
@@ -51,26 +48,24 @@ pub fn get_on_chain_entries(
     Ok(on_chain_entries)
 }
 
-pub fn update(
-    ans_host: &AnsHost<Daemon>,
-    diff: EntryDif<UncheckedContractEntry, String>,
-) -> Result<(), AbstractInterfaceError> {
+pub fn update_msgs(diff: EntryDif<UncheckedContractEntry, String>) -> Vec<ExecuteMsg> {
     let to_add: Vec<_> = diff.1.into_iter().collect();
     let to_remove: Vec<_> = diff.0.into_iter().collect();
+    let batch_size = crate::config::get().batch_sizes.contracts.get();
 
     // add the contracts
-    batch_execute_ans(&ans_host, &to_add, 10, |chunk| ExecuteMsg::UpdateContractAddresses {
+    let mut msgs = batch_ans_msgs(&to_add, batch_size, |chunk| ExecuteMsg::UpdateContractAddresses {
         to_add: chunk.to_vec(),
         to_remove: vec![],
-    })?;
+    });
 
     // remove the contracts
-    batch_execute_ans(&ans_host, &to_remove, 10, |chunk| {
+    msgs.extend(batch_ans_msgs(&to_remove, batch_size, |chunk| {
         ExecuteMsg::UpdateContractAddresses {
             to_add: vec![],
             to_remove: chunk.to_vec(),
         }
-    })?;
+    }));
 
-    Ok(())
+    msgs
 }
@@ -0,0 +1,437 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use abstract_core::ans_host::ExecuteMsg;
+use abstract_interface::{AbstractInterfaceError, AnsHost};
+use cosmos_sdk_proto::cosmos::authz::v1beta1::{
+    query_client::QueryClient as AuthzQueryClient, GenericAuthorization, MsgExec,
+    QueryGrantsRequest,
+};
+use cosmos_sdk_proto::cosmwasm::wasm::v1::{
+    AcceptedMessageKeysFilter, CombinedLimit, ContractExecutionAuthorization, MaxCallsLimit,
+    MsgExecuteContract,
+};
+use cosmos_sdk_proto::traits::Message;
+use cosmos_sdk_proto::Any;
+use cosmwasm_std::{wasm_execute, CosmosMsg};
+use cw_orch::prelude::*;
+use serde::Serialize;
+
+use crate::journal::Journal;
+
+const MSG_EXEC_TYPE_URL: &str = "/cosmos.authz.v1beta1.MsgExec";
+const MSG_EXECUTE_CONTRACT_TYPE_URL: &str = "/cosmwasm.wasm.v1.MsgExecuteContract";
+const GENERIC_AUTHORIZATION_TYPE_URL: &str = "/cosmos.authz.v1beta1.GenericAuthorization";
+const CONTRACT_EXECUTION_AUTHORIZATION_TYPE_URL: &str =
+    "/cosmwasm.wasm.v1.ContractExecutionAuthorization";
+const ALLOW_ALL_MESSAGES_FILTER_TYPE_URL: &str = "/cosmwasm.wasm.v1.AllowAllMessagesFilter";
+const ACCEPTED_MESSAGE_KEYS_FILTER_TYPE_URL: &str = "/cosmwasm.wasm.v1.AcceptedMessageKeysFilter";
+const MAX_CALLS_LIMIT_TYPE_URL: &str = "/cosmwasm.wasm.v1.MaxCallsLimit";
+const MAX_FUNDS_LIMIT_TYPE_URL: &str = "/cosmwasm.wasm.v1.MaxFundsLimit";
+const COMBINED_LIMIT_TYPE_URL: &str = "/cosmwasm.wasm.v1.CombinedLimit";
+
+/// ANS host messages the updater sends, an authz grant needs to allow all of them
+const ANS_UPDATE_MSG_KEYS: &[&str] = &[
+    "update_contract_addresses",
+    "update_asset_addresses",
+    "update_dexes",
+    "update_pools",
+];
+
+/// How the planned ANS host messages are brought on-chain
+#[derive(Debug, Clone, Default)]
+pub enum Execution {
+    /// Execute the messages on the ANS host from the daemon's sender
+    #[default]
+    Direct,
+    /// Wrap the messages in a proposal to the multisig or DAO that owns the ANS host
+    Proposal(ProposalConfig),
+    /// Execute the messages on behalf of `granter` through an authz grant given to the daemon's sender
+    Authz { granter: String },
+}
+
+impl Execution {
+    /// Number of transactions the daemon's sender broadcasts to bring `message_count` messages on-chain
+    pub fn transaction_count(&self, message_count: usize) -> usize {
+        match self {
+            Execution::Direct | Execution::Authz { .. } => message_count,
+            Execution::Proposal(config) if config.submit && message_count > 0 => 1,
+            Execution::Proposal(_) => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ProposalKind {
+    /// `cw3-fixed-multisig` or `cw3-flex-multisig`
+    Cw3,
+    /// DAO-DAO `dao-proposal-single` module
+    DaoProposalSingle,
+    /// DAO-DAO `dao-pre-propose-single` module, wraps the proposal in its own `propose` message
+    DaoPreProposeSingle,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProposalConfig {
+    pub kind: ProposalKind,
+    /// Address of the multisig or DAO proposal module the proposal is sent to
+    pub proposal_contract: String,
+    pub title: String,
+    pub description: String,
+    /// Submit the proposal from the daemon's sender, otherwise it's only printed
+    pub submit: bool,
+}
+
+/// Body shared by cw3 `Propose` and DAO-DAO `SingleChoiceProposeMsg`
+#[derive(Serialize, Debug)]
+pub struct Proposal {
+    pub title: String,
+    pub description: String,
+    pub msgs: Vec<CosmosMsg>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposeMsg {
+    Propose(Proposal),
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PreProposeMsg {
+    Propose { msg: ProposeMsg },
+}
+
+/// The message sent to the proposal contract, depending on the kind of contract
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum ProposalMsg {
+    Propose(ProposeMsg),
+    PrePropose(PreProposeMsg),
+}
+
+/// Bring `msgs` on-chain and record every broadcast in `journal`
+pub fn execute(
+    ans_host: &AnsHost<Daemon>,
+    msgs: Vec<ExecuteMsg>,
+    execution: &Execution,
+    journal: Option<&Journal>,
+) -> Result<(), AbstractInterfaceError> {
+    if msgs.is_empty() {
+        log::info!("ANS host is up to date, nothing to execute");
+        return Ok(());
+    }
+
+    match execution {
+        Execution::Direct => {
+            for msg in msgs {
+                let resp = ans_host.execute(&msg, None)?;
+                if let Some(journal) = journal {
+                    journal.record(ans_host.get_chain(), &msg, "direct", &resp)?;
+                }
+            }
+        }
+        Execution::Proposal(config) => {
+            let proposal = proposal_msg(ans_host, &msgs, config)?;
+            if config.submit {
+                let resp = ans_host
+                    .get_chain()
+                    .execute(&proposal, &[], &Addr::unchecked(&config.proposal_contract))
+                    .map_err(|e| CwOrchError::StdErr(e.to_string()))?;
+                log::info!(
+                    "Submitted ANS update proposal to {} in tx {}",
+                    config.proposal_contract,
+                    resp.txhash
+                );
+                if let Some(journal) = journal {
+                    for msg in &msgs {
+                        journal.record(ans_host.get_chain(), msg, "proposal", &resp)?;
+                    }
+                }
+            } else {
+                println!(
+                    "Proposal for {}:\n{}",
+                    config.proposal_contract,
+                    serde_json::to_string_pretty(&proposal)?
+                );
+            }
+        }
+        Execution::Authz { granter } => {
+            let chain = ans_host.get_chain();
+            let contract = ans_host.address()?.to_string();
+            for msg in msgs {
+                let execute_contract = MsgExecuteContract {
+                    sender: granter.clone(),
+                    contract: contract.clone(),
+                    msg: serde_json::to_vec(&msg)?,
+                    funds: vec![],
+                };
+                let exec = MsgExec {
+                    grantee: chain.sender().to_string(),
+                    msgs: vec![Any {
+                        type_url: MSG_EXECUTE_CONTRACT_TYPE_URL.to_string(),
+                        value: execute_contract.encode_to_vec(),
+                    }],
+                };
+                let any_msg = Any {
+                    type_url: MSG_EXEC_TYPE_URL.to_string(),
+                    value: exec.encode_to_vec(),
+                };
+                let resp = chain
+                    .rt_handle
+                    .block_on(chain.wallet().commit_tx_any(vec![any_msg], None))
+                    .map_err(|e| CwOrchError::StdErr(e.to_string()))?;
+                log::info!("Executed ANS update through authz in tx {}", resp.txhash);
+                if let Some(journal) = journal {
+                    journal.record(chain, &msg, "authz", &resp)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// How many ANS host executions an authorization allows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Coverage {
+    /// The authorization doesn't cover the ANS host update messages
+    None,
+    /// A call limit, every ANS host message is one call
+    Calls(u64),
+    Unlimited,
+}
+
+/// Check that `granter` granted the daemon's sender a non-expired authorization
+/// that covers every ANS host update message on the ANS host contract, for `calls` messages.
+pub fn assert_authz_grant(
+    ans_host: &AnsHost<Daemon>,
+    granter: &str,
+    calls: usize,
+) -> Result<(), AbstractInterfaceError> {
+    let chain = ans_host.get_chain();
+    let grantee = chain.sender().to_string();
+    let contract = ans_host.address()?.to_string();
+
+    let grants = chain
+        .rt_handle
+        .block_on(
+            AuthzQueryClient::new(chain.channel()).grants(QueryGrantsRequest {
+                granter: granter.to_string(),
+                grantee: grantee.clone(),
+                msg_type_url: MSG_EXECUTE_CONTRACT_TYPE_URL.to_string(),
+                pagination: None,
+            }),
+        )
+        .map_err(|e| CwOrchError::StdErr(e.to_string()))?
+        .into_inner()
+        .grants;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| CwOrchError::StdErr(e.to_string()))?
+        .as_secs() as i64;
+
+    let coverage = grants
+        .iter()
+        .filter(|grant| {
+            grant
+                .expiration
+                .as_ref()
+                .map_or(true, |expiration| expiration.seconds > now)
+        })
+        .filter_map(|grant| grant.authorization.as_ref())
+        .map(|authorization| authorization_covers(authorization, &contract))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .max()
+        .unwrap_or(Coverage::None);
+
+    check_coverage(coverage, calls).map_err(|problem| {
+        CwOrchError::StdErr(format!(
+            "authz grant from {granter} to {grantee} on the ANS host {contract}: {problem}"
+        ))
+    })?;
+    log::info!("Authz grant from {granter} to {grantee} covers the ANS host {contract}");
+    Ok(())
+}
+
+fn check_coverage(coverage: Coverage, calls: usize) -> Result<(), String> {
+    match coverage {
+        Coverage::None => Err(format!(
+            "no grant covers executing {}",
+            ANS_UPDATE_MSG_KEYS.join(", ")
+        )),
+        Coverage::Calls(remaining) if (remaining as usize) < calls => Err(format!(
+            "the grant allows {remaining} more calls, the run sends {calls} messages"
+        )),
+        Coverage::Calls(_) | Coverage::Unlimited => Ok(()),
+    }
+}
+
+/// Calls the limit of a contract grant allows. The updater sends no funds, a funds limit doesn't restrict it.
+fn limit_coverage(limit: Option<&Any>) -> Result<Coverage, AbstractInterfaceError> {
+    let decode_err = |e: cosmos_sdk_proto::prost::DecodeError| CwOrchError::StdErr(e.to_string());
+    let Some(limit) = limit else {
+        return Ok(Coverage::Unlimited);
+    };
+    Ok(match limit.type_url.as_str() {
+        MAX_CALLS_LIMIT_TYPE_URL => Coverage::Calls(
+            MaxCallsLimit::decode(limit.value.as_slice())
+                .map_err(decode_err)?
+                .remaining,
+        ),
+        COMBINED_LIMIT_TYPE_URL => Coverage::Calls(
+            CombinedLimit::decode(limit.value.as_slice())
+                .map_err(decode_err)?
+                .calls_remaining,
+        ),
+        MAX_FUNDS_LIMIT_TYPE_URL => Coverage::Unlimited,
+        // A limit we can't interpret might not allow any call
+        _ => Coverage::None,
+    })
+}
+
+fn authorization_covers(
+    authorization: &Any,
+    contract: &str,
+) -> Result<Coverage, AbstractInterfaceError> {
+    let decode_err = |e: cosmos_sdk_proto::prost::DecodeError| CwOrchError::StdErr(e.to_string());
+    match authorization.type_url.as_str() {
+        GENERIC_AUTHORIZATION_TYPE_URL => {
+            let generic =
+                GenericAuthorization::decode(authorization.value.as_slice()).map_err(decode_err)?;
+            Ok(if generic.msg == MSG_EXECUTE_CONTRACT_TYPE_URL {
+                Coverage::Unlimited
+            } else {
+                Coverage::None
+            })
+        }
+        CONTRACT_EXECUTION_AUTHORIZATION_TYPE_URL => {
+            let contract_authorization =
+                ContractExecutionAuthorization::decode(authorization.value.as_slice())
+                    .map_err(decode_err)?;
+            for grant in contract_authorization.grants {
+                if grant.contract != contract {
+                    continue;
+                }
+                let Some(filter) = grant.filter else {
+                    continue;
+                };
+                match filter.type_url.as_str() {
+                    ALLOW_ALL_MESSAGES_FILTER_TYPE_URL => {
+                        return limit_coverage(grant.limit.as_ref())
+                    }
+                    ACCEPTED_MESSAGE_KEYS_FILTER_TYPE_URL => {
+                        let keys = AcceptedMessageKeysFilter::decode(filter.value.as_slice())
+                            .map_err(decode_err)?
+                            .keys;
+                        if ANS_UPDATE_MSG_KEYS
+                            .iter()
+                            .all(|key| keys.iter().any(|k| k == key))
+                        {
+                            return limit_coverage(grant.limit.as_ref());
+                        }
+                    }
+                    // AcceptedMessagesFilter only allows exact messages, which we can't know upfront
+                    _ => {}
+                }
+            }
+            Ok(Coverage::None)
+        }
+        _ => Ok(Coverage::None),
+    }
+}
+
+/// Wrap the ANS host messages in `WasmMsg::Execute` calls and build the proposal message for `config`
+pub fn proposal_msg(
+    ans_host: &AnsHost<Daemon>,
+    msgs: &[ExecuteMsg],
+    config: &ProposalConfig,
+) -> Result<ProposalMsg, AbstractInterfaceError> {
+    let ans_host_addr = ans_host.address()?;
+    let msgs = msgs
+        .iter()
+        .map(|msg| Ok(wasm_execute(&ans_host_addr, msg, vec![])?.into()))
+        .collect::<Result<Vec<CosmosMsg>, AbstractInterfaceError>>()?;
+
+    let propose = ProposeMsg::Propose(Proposal {
+        title: config.title.clone(),
+        description: config.description.clone(),
+        msgs,
+    });
+
+    Ok(match config.kind {
+        ProposalKind::Cw3 | ProposalKind::DaoProposalSingle => ProposalMsg::Propose(propose),
+        ProposalKind::DaoPreProposeSingle => {
+            ProposalMsg::PrePropose(PreProposeMsg::Propose { msg: propose })
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use cosmos_sdk_proto::cosmwasm::wasm::v1::ContractGrant;
+
+    use super::*;
+
+    const CONTRACT: &str = "juno1anshost";
+
+    fn any(type_url: &str, value: Vec<u8>) -> Any {
+        Any {
+            type_url: type_url.to_string(),
+            value,
+        }
+    }
+
+    fn contract_authorization(limit: Option<Any>) -> Any {
+        let authorization = ContractExecutionAuthorization {
+            grants: vec![ContractGrant {
+                contract: CONTRACT.to_string(),
+                limit,
+                filter: Some(any(ALLOW_ALL_MESSAGES_FILTER_TYPE_URL, vec![])),
+            }],
+        };
+        any(
+            CONTRACT_EXECUTION_AUTHORIZATION_TYPE_URL,
+            authorization.encode_to_vec(),
+        )
+    }
+
+    fn max_calls(remaining: u64) -> Option<Any> {
+        Some(any(
+            MAX_CALLS_LIMIT_TYPE_URL,
+            MaxCallsLimit { remaining }.encode_to_vec(),
+        ))
+    }
+
+    #[test]
+    fn decodes_the_call_limit() {
+        let coverage = |limit| authorization_covers(&contract_authorization(limit), CONTRACT);
+
+        assert_eq!(coverage(None).unwrap(), Coverage::Unlimited);
+        assert_eq!(coverage(max_calls(3)).unwrap(), Coverage::Calls(3));
+        let combined = CombinedLimit {
+            calls_remaining: 2,
+            amounts: vec![],
+        };
+        assert_eq!(
+            coverage(Some(any(COMBINED_LIMIT_TYPE_URL, combined.encode_to_vec()))).unwrap(),
+            Coverage::Calls(2)
+        );
+        assert_eq!(
+            authorization_covers(&contract_authorization(None), "juno1other").unwrap(),
+            Coverage::None
+        );
+    }
+
+    #[test]
+    fn rejects_exhausted_or_too_small_grants() {
+        let coverage =
+            |limit| authorization_covers(&contract_authorization(limit), CONTRACT).unwrap();
+
+        assert!(check_coverage(coverage(max_calls(0)), 1).is_err());
+        assert!(check_coverage(coverage(max_calls(2)), 3).is_err());
+        assert!(check_coverage(coverage(max_calls(3)), 3).is_ok());
+        assert!(check_coverage(coverage(None), 300).is_ok());
+        assert!(check_coverage(Coverage::None, 0).is_err());
+    }
+}
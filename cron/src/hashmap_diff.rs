@@ -77,6 +77,7 @@ mod test {
     use anyhow::Result as AnyResult;
 
     use crate::assets::get_scraped_entries;
+    use crate::ScrapedFiles;
     const CHAIN: ChainInfo = JUNO_1;
 
     #[test]
@@ -86,7 +87,8 @@ mod test {
         let chain_name = chain.chain_name;
         let chain_id = chain.chain_id.to_string();
 
-        let scraped = get_scraped_entries(&chain_name, &chain_id).unwrap();
+        let files = ScrapedFiles::fetch(&["assets"]).unwrap();
+        let scraped = get_scraped_entries(&files, &chain_name, &chain_id).unwrap();
 
         assert!(!scraped.is_empty());
         Ok(())
@@ -99,7 +101,8 @@ mod test {
         let chain_name = chain.chain_name;
         let chain_id = chain.chain_id.to_string();
 
-        let scraped = get_scraped_entries(&chain_name, &chain_id).unwrap();
+        let files = ScrapedFiles::fetch(&["assets"]).unwrap();
+        let scraped = get_scraped_entries(&files, &chain_name, &chain_id).unwrap();
 
         let dummy_scraped = scraped.clone();
 
@@ -117,7 +120,8 @@ mod test {
         let chain_name = chain.chain_name;
         let chain_id = chain.chain_id.to_string();
 
-        let scraped = get_scraped_entries(&chain_name, &chain_id).unwrap();
+        let files = ScrapedFiles::fetch(&["assets"]).unwrap();
+        let scraped = get_scraped_entries(&files, &chain_name, &chain_id).unwrap();
 
         let mut dummy_scraped = scraped.clone();
         let first_key = dummy_scraped.keys().next().unwrap().clone();
@@ -141,7 +145,8 @@ mod test {
         let chain_name = chain.chain_name;
         let chain_id = chain.chain_id.to_string();
 
-        let scraped = get_scraped_entries(&chain_name, &chain_id).unwrap();
+        let files = ScrapedFiles::fetch(&["assets"]).unwrap();
+        let scraped = get_scraped_entries(&files, &chain_name, &chain_id).unwrap();
 
         let mut dummy_scraped = scraped.clone();
 
@@ -165,7 +170,8 @@ mod test {
         let chain_name = chain.chain_name;
         let chain_id = chain.chain_id.to_string();
 
-        let scraped = get_scraped_entries(&chain_name, &chain_id).unwrap();
+        let files = ScrapedFiles::fetch(&["assets"]).unwrap();
+        let scraped = get_scraped_entries(&files, &chain_name, &chain_id).unwrap();
 
         let mut dummy_scraped = scraped.clone();
         let first_key = dummy_scraped.keys().next().unwrap().clone();
@@ -185,7 +191,8 @@ mod test {
         let chain_name = chain.chain_name;
         let chain_id = chain.chain_id.to_string();
 
-        let scraped = get_scraped_entries(&chain_name, &chain_id).unwrap();
+        let files = ScrapedFiles::fetch(&["assets"]).unwrap();
+        let scraped = get_scraped_entries(&files, &chain_name, &chain_id).unwrap();
 
         let mut dummy_scraped = scraped.clone();
         let first_key = dummy_scraped.keys().next().unwrap().clone();
@@ -214,7 +221,8 @@ mod test {
         let chain_name = chain.chain_name;
         let chain_id = chain.chain_id.to_string();
 
-        let scraped = get_scraped_entries(&chain_name, &chain_id).unwrap();
+        let files = ScrapedFiles::fetch(&["assets"]).unwrap();
+        let scraped = get_scraped_entries(&files, &chain_name, &chain_id).unwrap();
 
         let mut dummy_scraped = scraped.clone();
         let first_key = dummy_scraped.keys().next().unwrap().clone();
@@ -243,7 +251,8 @@ mod test {
         let chain_name = chain.chain_name;
         let chain_id = chain.chain_id.to_string();
 
-        let scraped = get_scraped_entries(&chain_name, &chain_id).unwrap();
+        let files = ScrapedFiles::fetch(&["assets"]).unwrap();
+        let scraped = get_scraped_entries(&files, &chain_name, &chain_id).unwrap();
 
         let mut dummy_scraped = scraped.clone();
         let first_key = dummy_scraped.keys().next().unwrap().clone();
@@ -0,0 +1,240 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use abstract_core::ans_host::ExecuteMsg;
+use abstract_core::objects::pool_id::{PoolAddressBase, UncheckedPoolAddress};
+use abstract_core::objects::{UncheckedChannelEntry, UncheckedContractEntry};
+use abstract_interface::AbstractInterfaceError;
+use cw_orch::daemon::CosmTxResponse;
+use cw_orch::prelude::*;
+use cw_orch::state::ChainState;
+use serde::{Deserialize, Serialize};
+
+use crate::EntityType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MsgKind {
+    Add,
+    Remove,
+}
+
+/// One broadcast ANS host message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalRecord {
+    pub chain_id: String,
+    pub entity: EntityType,
+    pub kind: MsgKind,
+    pub keys: Vec<String>,
+    /// How the message was brought on-chain: `direct`, `authz` or `proposal`
+    pub execution: String,
+    pub tx_hash: String,
+    pub height: u64,
+    pub gas_wanted: u64,
+    pub gas_used: u64,
+    /// `gas_wanted` at the minimum gas price of the chain's fee token, not read from the transaction.
+    /// Journals written before it was renamed call it `fee`.
+    #[serde(alias = "fee")]
+    pub estimated_fee: String,
+    pub timestamp: String,
+    pub msg: ExecuteMsg,
+}
+
+/// Append-only JSON lines file that records every broadcast of a run
+#[derive(Debug, Clone)]
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    /// Start a new run journal in `dir`, named after the time the run started
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self, AbstractInterfaceError> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir).map_err(|e| CwOrchError::StdErr(e.to_string()))?;
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| CwOrchError::StdErr(e.to_string()))?
+            .as_secs();
+        Ok(Self {
+            path: dir.join(format!("run-{started}.jsonl")),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Where the pre-update state of `chain_id` is stored for this run
+    pub fn snapshot_path(&self, chain_id: &str) -> PathBuf {
+        snapshot_path(&self.path, chain_id)
+    }
+
+    /// Record the transaction that executed `msg` on `chain`
+    pub fn record(
+        &self,
+        chain: &Daemon,
+        msg: &ExecuteMsg,
+        execution: &str,
+        resp: &CosmTxResponse,
+    ) -> Result<(), AbstractInterfaceError> {
+        let Some((entity, kind, keys)) = describe_msg(msg) else {
+            return Ok(());
+        };
+        let chain_data = &chain.state().chain_data;
+        let estimated_fee = chain_data
+            .fees
+            .fee_tokens
+            .first()
+            .map(|fee_token| {
+                let amount = (resp.gas_wanted as f64 * fee_token.fixed_min_gas_price).ceil();
+                format!("{}{}", amount as u128, fee_token.denom)
+            })
+            .unwrap_or_default();
+
+        self.append(&JournalRecord {
+            chain_id: chain_data.chain_id.to_string(),
+            entity,
+            kind,
+            keys,
+            execution: execution.to_string(),
+            tx_hash: resp.txhash.clone(),
+            height: resp.height,
+            gas_wanted: resp.gas_wanted,
+            gas_used: resp.gas_used,
+            estimated_fee,
+            timestamp: resp.timestamp.to_string(),
+            msg: msg.clone(),
+        })
+    }
+
+    pub fn append(&self, record: &JournalRecord) -> Result<(), AbstractInterfaceError> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| CwOrchError::StdErr(e.to_string()))?;
+        // One write per record, so chains that run concurrently don't interleave their lines
+        let line = format!("{}\n", serde_json::to_string(record)?);
+        file.write_all(line.as_bytes())
+            .map_err(|e| CwOrchError::StdErr(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Read back all the records of a run journal
+    pub fn read(path: impl AsRef<Path>) -> Result<Vec<JournalRecord>, AbstractInterfaceError> {
+        let file = File::open(path).map_err(|e| CwOrchError::StdErr(e.to_string()))?;
+        let mut records = vec![];
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| CwOrchError::StdErr(e.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            records.push(serde_json::from_str(&line)?);
+        }
+        Ok(records)
+    }
+}
+
+/// Snapshot file of `chain_id` that belongs to the run journal at `journal_path`
+pub fn snapshot_path(journal_path: &Path, chain_id: &str) -> PathBuf {
+    journal_path.with_extension(format!("{chain_id}.snapshot.json"))
+}
+
+/// Entity, kind and keys of the entries an ANS host update message touches
+pub fn describe_msg(msg: &ExecuteMsg) -> Option<(EntityType, MsgKind, Vec<String>)> {
+    let (entity, added, removed): (_, Vec<String>, Vec<String>) = match msg {
+        ExecuteMsg::UpdateContractAddresses { to_add, to_remove } => (
+            EntityType::Contracts,
+            to_add
+                .iter()
+                .map(|(entry, _)| contract_key(entry))
+                .collect(),
+            to_remove.iter().map(contract_key).collect(),
+        ),
+        ExecuteMsg::UpdateAssetAddresses { to_add, to_remove } => (
+            EntityType::Assets,
+            to_add.iter().map(|(name, _)| name.clone()).collect(),
+            to_remove.clone(),
+        ),
+        ExecuteMsg::UpdateChannels { to_add, to_remove } => (
+            EntityType::Channels,
+            to_add.iter().map(|(entry, _)| channel_key(entry)).collect(),
+            to_remove.iter().map(channel_key).collect(),
+        ),
+        ExecuteMsg::UpdateDexes { to_add, to_remove } => {
+            (EntityType::Dexes, to_add.clone(), to_remove.clone())
+        }
+        ExecuteMsg::UpdatePools { to_add, to_remove } => (
+            EntityType::Pools,
+            to_add
+                .iter()
+                .map(|(address, _)| pool_address_key(address))
+                .collect(),
+            to_remove.iter().map(|id| id.to_string()).collect(),
+        ),
+        _ => return None,
+    };
+    // The updater never mixes additions and removals in one message
+    if added.is_empty() {
+        Some((entity, MsgKind::Remove, removed))
+    } else {
+        Some((entity, MsgKind::Add, added))
+    }
+}
+
+pub fn contract_key(entry: &UncheckedContractEntry) -> String {
+    format!("{}:{}", entry.protocol, entry.contract)
+}
+
+pub fn channel_key(entry: &UncheckedChannelEntry) -> String {
+    format!("{}:{}", entry.connected_chain, entry.protocol)
+}
+
+pub fn pool_address_key(address: &UncheckedPoolAddress) -> String {
+    match address {
+        PoolAddressBase::Id(id) => id.to_string(),
+        PoolAddressBase::Contract(contract) => contract.clone(),
+        PoolAddressBase::SeparateAddresses { swap, liquidity } => format!("{swap},{liquidity}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use abstract_core::objects::UniquePoolId;
+    use cw_asset::AssetInfoBase;
+
+    use super::*;
+
+    #[test]
+    fn describes_asset_additions() {
+        let msg = ExecuteMsg::UpdateAssetAddresses {
+            to_add: vec![(
+                "juno>juno".to_string(),
+                AssetInfoBase::Native("ujuno".to_string()),
+            )],
+            to_remove: vec![],
+        };
+
+        let (entity, kind, keys) = describe_msg(&msg).unwrap();
+
+        assert_eq!(entity, EntityType::Assets);
+        assert_eq!(kind, MsgKind::Add);
+        assert_eq!(keys, vec!["juno>juno"]);
+    }
+
+    #[test]
+    fn describes_pool_removals() {
+        let msg = ExecuteMsg::UpdatePools {
+            to_add: vec![],
+            to_remove: vec![UniquePoolId::new(4), UniquePoolId::new(7)],
+        };
+
+        let (entity, kind, keys) = describe_msg(&msg).unwrap();
+
+        assert_eq!(entity, EntityType::Pools);
+        assert_eq!(kind, MsgKind::Remove);
+        assert_eq!(keys, vec!["4", "7"]);
+    }
+}
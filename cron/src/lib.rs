@@ -1,4 +1,3 @@
-use std::cmp::min;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
@@ -10,95 +9,138 @@ use abstract_core::objects::UniquePoolId;
 use abstract_interface::AbstractInterfaceError;
 use abstract_interface::AnsHost;
 use cw_asset::AssetInfoBase;
-use cw_orch::{
-    daemon::ChainInfo,
-    prelude::{
-        *,
-        networks::{HARPOON_4, JUNO_1, OSMO_5, PHOENIX_1, PION_1, PISCO_1, UNI_6},
-    },
-};
-use cw_orch::daemon::Daemon;
-use cw_orch::daemon::DaemonAsyncBuilder;
+use cw_orch::prelude::*;
+use cw_orch::daemon::{ChainRegistryData as ChainData, Daemon};
 use cw_orch::state::ChainState;
+use crate::execution::Execution;
+use crate::journal::Journal;
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tokio::runtime::Runtime;
 
-const GAS_TO_DEPLOY: u64 = 60_000_000;
-pub const SUPPORTED_CHAINS: &[ChainInfo] = &[
-    UNI_6, OSMO_5, PISCO_1, PHOENIX_1, JUNO_1, PION_1, NEUTRON_1, HARPOON_4,
-];
-
-pub const NEUTRON_1: ChainInfo = ChainInfo {
-    kind: cw_orch::daemon::ChainKind::Mainnet,
-    chain_id: "neutron-1",
-    gas_denom: "untrn",
-    gas_price: 0.001,
-    grpc_urls: &["https://grpc.novel.remedy.tm.p2p.org"],
-    network_info: networks::neutron::NEUTRON_NETWORK,
-    lcd_url: Some("https://rest-kralum.neutron-1.neutron.org"),
-    fcd_url: None,
-};
-
-pub async fn assert_wallet_balance<'a>(mut chains: &'a [ChainInfo<'a>]) -> &'a [ChainInfo<'a>] {
-    if chains.is_empty() {
-        chains = SUPPORTED_CHAINS;
-    }
-    // check that the wallet has enough gas on all the chains we want to support
-    for chain_info in chains {
-        let chain = DaemonAsyncBuilder::default()
-            .chain(chain_info.clone())
-            .build()
-            .await
-            .unwrap();
-        let fee_token = chain.state.as_ref().chain_data.fees.fee_tokens[0].clone();
-        let fee = (GAS_TO_DEPLOY as f64 * fee_token.fixed_min_gas_price) as u128;
-        let bank = chain.query_client::<queriers::Bank>();
-        let balance = bank
-            .balance(chain.sender(), Some(fee_token.denom.clone()))
-            .await
-            .unwrap()[0]
-            .clone();
-
-        log::debug!(
-            "Checking balance {} on chain {}, address {}. Expecting {}{}",
-            balance.amount,
-            chain_info.chain_id,
-            chain.sender(),
-            fee,
-            fee_token.denom.as_str()
-        );
-        if fee > balance.amount.parse().unwrap() {
-            panic!("Not enough funds on chain {} to deploy the contract. Needed: {}{} but only have: {}{}", chain_info.chain_id, fee, fee_token.denom.as_str(), balance.amount, fee_token.denom);
-        }
-        // check if we have enough funds
-    }
-
-    chains
-}
-
 pub mod assets;
+pub mod balance;
+pub mod bootstrap;
 pub mod channels;
+pub mod config;
 pub mod contracts;
+pub mod execution;
 pub mod hashmap_diff;
+pub mod journal;
+pub mod parallel;
+pub mod plan;
 pub mod pools;
+pub mod rollback;
+pub mod safety;
+pub mod sections;
+pub mod validation;
+pub mod verify;
+
+/// Location of a scraped file, `scrape_url` is either a base URL or a local directory
+pub fn scraped_file_url(suffix: &str) -> String {
+    format!("{}{}.json", config::get().scrape_url, suffix)
+}
 
-const ANS_SCRAPE_URL: &str =
-    "https://raw.githubusercontent.com/AbstractSDK/ans-scraper/mainline/out/";
+/// A scraped file that couldn't be downloaded or read, its content is never looked at
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct FetchError(pub String);
 
-/// get some json
-pub fn get_scraped_json_data(suffix: &str) -> Value {
+/// get the raw content of a scraped file
+pub fn get_scraped_text(suffix: &str) -> Result<String, FetchError> {
+    let url = scraped_file_url(suffix);
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return std::fs::read_to_string(&url)
+            .map_err(|e| FetchError(format!("failed to read {url}: {e}")));
+    }
     let client = Client::new();
-    let url = format!("{}{}.json", ANS_SCRAPE_URL, suffix);
-    let rt = Runtime::new().unwrap();
+    let rt = Runtime::new().map_err(|e| FetchError(e.to_string()))?;
     rt.block_on(async {
-        let response = client.get(&url).send().await.unwrap();
-        let json: Value = response.json().await.unwrap();
-        json
+        let response = client
+            .get(&url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| FetchError(format!("failed to fetch {url}: {e}")))?;
+        response
+            .text()
+            .await
+            .map_err(|e| FetchError(format!("failed to read {url}: {e}")))
     })
 }
 
-#[derive(Default, Debug)]
+/// The scraped files of a run, fetched once and read by every chain
+#[derive(Debug, Default)]
+pub struct ScrapedFiles {
+    /// Raw content of every fetched file, by name without `.json`
+    texts: HashMap<String, String>,
+    /// The parsed content, or why it isn't valid JSON
+    values: HashMap<String, Result<Value, String>>,
+}
+
+impl ScrapedFiles {
+    /// Fetch `files` from `scrape_url`. Only fails when a file can't be fetched,
+    /// a file that isn't valid JSON fails where it's read.
+    pub fn fetch(files: &[&str]) -> Result<Self, FetchError> {
+        let mut scraped_files = Self::default();
+        for file in files {
+            scraped_files.insert(file, get_scraped_text(file)?);
+        }
+        Ok(scraped_files)
+    }
+
+    pub fn insert(&mut self, file: &str, text: String) {
+        let value = serde_json::from_str(&text)
+            .map_err(|e| format!("invalid json in {file}.json: {e}"));
+        self.values.insert(file.to_string(), value);
+        self.texts.insert(file.to_string(), text);
+    }
+
+    pub fn contains(&self, file: &str) -> bool {
+        self.texts.contains_key(file)
+    }
+
+    pub fn text(&self, file: &str) -> Result<&str, AbstractInterfaceError> {
+        self.texts
+            .get(file)
+            .map(String::as_str)
+            .ok_or_else(|| not_fetched(file))
+    }
+
+    pub fn json(&self, file: &str) -> Result<&Value, AbstractInterfaceError> {
+        match self.values.get(file) {
+            Some(Ok(value)) => Ok(value),
+            Some(Err(err)) => Err(CwOrchError::StdErr(err.clone()).into()),
+            None => Err(not_fetched(file)),
+        }
+    }
+}
+
+fn not_fetched(file: &str) -> AbstractInterfaceError {
+    CwOrchError::StdErr(format!("{file}.json wasn't fetched for this run")).into()
+}
+
+/// The kinds of entries the ANS host stores
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityType {
+    Contracts,
+    Assets,
+    Dexes,
+    Pools,
+    Channels,
+}
+
+/// The entities that are synced by default, channels are only synced when explicitly selected
+pub const ANS_DATA_ENTITIES: &[EntityType] = &[
+    EntityType::Contracts,
+    EntityType::Assets,
+    EntityType::Dexes,
+    EntityType::Pools,
+];
+
+#[derive(Default, Debug, Clone)]
 pub struct AnsData {
     pub contracts: HashMap<UncheckedContractEntry, String>,
     pub assets: HashMap<String, AssetInfoBase<String>>,
@@ -121,16 +163,92 @@ pub struct AnsDataDiff {
     ),
 }
 
-pub fn get_scraped_entries(ans_host: &AnsHost<Daemon>) -> Result<AnsData, AbstractInterfaceError> {
-    let chain_name = &ans_host.get_chain().state().chain_data.chain_name;
-    let chain_id = ans_host.get_chain().state().chain_data.chain_id.to_string();
+impl AnsDataDiff {
+    /// Number of added or updated and removed entries of every entity type that changes
+    pub fn summary(&self) -> Vec<(EntityType, usize, usize)> {
+        [
+            (EntityType::Contracts, self.contracts.1.len(), self.contracts.0.len()),
+            (EntityType::Assets, self.assets.1.len(), self.assets.0.len()),
+            (EntityType::Dexes, self.dexes.1.len(), self.dexes.0.len()),
+            (EntityType::Pools, self.pools.1.len(), self.pools.0.len()),
+        ]
+        .into_iter()
+        .filter(|(_, added, removed)| added + removed > 0)
+        .collect()
+    }
 
-    let contracts = crate::contracts::get_scraped_entries(chain_name, &chain_id)?;
-    let mut assets = crate::assets::get_scraped_entries(chain_name, &chain_id)?;
-    let (pools, dexes, lp_assets) = crate::pools::get_scraped_entries(chain_name, &chain_id)?;
+    /// Returns true when there is nothing to add or remove
+    pub fn is_empty(&self) -> bool {
+        self.contracts.0.is_empty()
+            && self.contracts.1.is_empty()
+            && self.assets.0.is_empty()
+            && self.assets.1.is_empty()
+            && self.dexes.0.is_empty()
+            && self.dexes.1.is_empty()
+            && self.pools.0.is_empty()
+            && self.pools.1.is_empty()
+    }
+}
+
+impl AnsData {
+    /// Drop the entries of the entity types that aren't in `entities`
+    pub fn retain_entities(&mut self, entities: &[EntityType]) {
+        if !entities.contains(&EntityType::Contracts) {
+            self.contracts.clear();
+        }
+        if !entities.contains(&EntityType::Assets) {
+            self.assets.clear();
+        }
+        if !entities.contains(&EntityType::Dexes) {
+            self.dexes.clear();
+        }
+        if !entities.contains(&EntityType::Pools) {
+            self.pools.clear();
+        }
+    }
+}
+
+/// Scraped entries of the entity types in `entities`
+pub fn get_scraped_entries(
+    ans_host: &AnsHost<Daemon>,
+    files: &ScrapedFiles,
+    entities: &[EntityType],
+) -> Result<AnsData, AbstractInterfaceError> {
+    get_chain_scraped_entries(&ans_host.get_chain().state().chain_data, files, entities)
+}
+
+/// The scraped entries of the chain of `chain_data`, without a connection to the chain
+pub fn get_chain_scraped_entries(
+    chain_data: &ChainData,
+    files: &ScrapedFiles,
+    entities: &[EntityType],
+) -> Result<AnsData, AbstractInterfaceError> {
+    let chain_name = &config::get().scraped_chain_name(&chain_data.chain_name);
+    let chain_id = chain_data.chain_id.to_string();
+    let selected = |entity| entities.contains(&entity);
+
+    let contracts = if selected(EntityType::Contracts) {
+        crate::contracts::get_scraped_entries(files, chain_name, &chain_id)?
+    } else {
+        HashMap::new()
+    };
+    let mut assets = if selected(EntityType::Assets) {
+        crate::assets::get_scraped_entries(files, chain_name, &chain_id)?
+    } else {
+        HashMap::new()
+    };
+    // The pools data also holds the dexes and the LP assets
+    let (pools, dexes, lp_assets) = if selected(EntityType::Assets)
+        || selected(EntityType::Dexes)
+        || selected(EntityType::Pools)
+    {
+        crate::pools::get_scraped_entries(files, chain_name, &chain_id)?
+    } else {
+        Default::default()
+    };
     assets.extend(lp_assets);
 
-    Ok(AnsData {
+    let mut scraped_entries = AnsData {
         contracts: contracts.into_iter().collect(),
         assets,
         dexes: dexes.into_iter().map(|v| (v.clone(), v)).collect(),
@@ -138,14 +256,38 @@ pub fn get_scraped_entries(ans_host: &AnsHost<Daemon>) -> Result<AnsData, Abstra
             .into_iter()
             .map(|(a, m)| (a, (UniquePoolId::new(0), m)))
             .collect(),
-    })
+    };
+    scraped_entries.retain_entities(entities);
+    Ok(scraped_entries)
 }
 
-pub fn get_on_chain_entries(ans_host: &AnsHost<Daemon>) -> Result<AnsData, AbstractInterfaceError> {
-    let contracts = crate::contracts::get_on_chain_entries(ans_host)?;
-    let assets = crate::assets::get_on_chain_entries(ans_host)?;
-    let pools = crate::pools::get_on_chain_entries(ans_host)?;
-    let dexes = crate::pools::get_on_chain_dexes(ans_host)?;
+/// On-chain entries of the entity types in `entities`
+pub fn get_on_chain_entries(
+    ans_host: &AnsHost<Daemon>,
+    entities: &[EntityType],
+) -> Result<AnsData, AbstractInterfaceError> {
+    let selected = |entity| entities.contains(&entity);
+
+    let contracts = if selected(EntityType::Contracts) {
+        crate::contracts::get_on_chain_entries(ans_host)?
+    } else {
+        HashMap::new()
+    };
+    let assets = if selected(EntityType::Assets) {
+        crate::assets::get_on_chain_entries(ans_host)?
+    } else {
+        HashMap::new()
+    };
+    let pools = if selected(EntityType::Pools) {
+        crate::pools::get_on_chain_entries(ans_host)?
+    } else {
+        HashMap::new()
+    };
+    let dexes = if selected(EntityType::Dexes) {
+        crate::pools::get_on_chain_dexes(ans_host)?
+    } else {
+        vec![]
+    };
 
     Ok(AnsData {
         contracts,
@@ -196,13 +338,34 @@ pub fn diff(
     })
 }
 
-pub fn update(ans_host: &AnsHost<Daemon>, diff: AnsDataDiff) -> Result<(), AbstractInterfaceError> {
-    contracts::update(ans_host, diff.contracts)?;
-    assets::update(ans_host, diff.assets)?;
-    pools::update_dexes(ans_host, diff.dexes)?;
-    pools::update(ans_host, diff.pools)?;
+/// Build the ANS host messages that apply `diff`, in the order they need to be executed
+pub fn update_msgs(diff: AnsDataDiff) -> Vec<ans_host::ExecuteMsg> {
+    let mut msgs = contracts::update_msgs(diff.contracts);
+    msgs.extend(assets::update_msgs(diff.assets));
+    msgs.extend(pools::update_dexes_msgs(diff.dexes));
+    msgs.extend(pools::update_msgs(diff.pools));
+    msgs
+}
+
+pub fn update(
+    ans_host: &AnsHost<Daemon>,
+    diff: AnsDataDiff,
+    execution: &Execution,
+    journal: Option<&Journal>,
+) -> Result<(), AbstractInterfaceError> {
+    execution::execute(ans_host, update_msgs(diff), execution, journal)
+}
 
-    Ok(())
+/// Split `items` in chunks of `chunk_size` and build one ANS host message per chunk
+pub fn batch_ans_msgs<T, MsgBuilder>(
+    items: &[T],
+    chunk_size: usize,
+    msg_builder: MsgBuilder,
+) -> Vec<ans_host::ExecuteMsg>
+    where
+        MsgBuilder: FnMut(&[T]) -> ans_host::ExecuteMsg,
+{
+    items.chunks(chunk_size).map(msg_builder).collect()
 }
 
 /// Temporarily include batch function here until it's moved to abstract_interface
@@ -211,16 +374,17 @@ pub fn batch_execute_ans<T, MsgBuilder>(
     ans_host: &AnsHost<Daemon>,
     items: &[T],
     chunk_size: usize,
-    mut msg_builder: MsgBuilder,
+    msg_builder: MsgBuilder,
+    execution: &Execution,
+    journal: Option<&Journal>,
 ) -> Result<(), crate::AbstractInterfaceError>
     where
         MsgBuilder: FnMut(&[T]) -> ans_host::ExecuteMsg,
 {
-    let mut i = 0;
-    while i < items.len() {
-        let chunk = &items[i..min(i + chunk_size, items.len())];
-        i += chunk.len();
-        ans_host.execute(&msg_builder(chunk), None)?;
-    }
-    Ok(())
+    execution::execute(
+        ans_host,
+        batch_ans_msgs(items, chunk_size, msg_builder),
+        execution,
+        journal,
+    )
 }
@@ -0,0 +1,81 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::thread;
+
+/// Run `f` on every item with at most `concurrency` items in flight.
+/// Results are returned in the order of `items`, a panic in `f` only fails its own item.
+pub fn run_concurrently<I, R, F>(items: Vec<I>, concurrency: usize, f: F) -> Vec<Result<R, String>>
+where
+    I: Send,
+    R: Send,
+    F: Fn(I) -> R + Sync,
+{
+    let len = items.len();
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let results = Mutex::new(Vec::with_capacity(len));
+
+    thread::scope(|scope| {
+        for _ in 0..concurrency.clamp(1, len.max(1)) {
+            scope.spawn(|| loop {
+                let Some((index, item)) = queue.lock().unwrap().next() else {
+                    break;
+                };
+                let result =
+                    panic::catch_unwind(AssertUnwindSafe(|| f(item))).map_err(panic_message);
+                results.lock().unwrap().push((index, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panicked".to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn keeps_order_and_isolates_panics() {
+        let results = run_concurrently(vec![1, 2, 3, 4], 2, |i| {
+            if i == 2 {
+                panic!("chain {i} is down");
+            }
+            i * 10
+        });
+
+        assert_eq!(
+            results,
+            vec![Ok(10), Err("chain 2 is down".to_string()), Ok(30), Ok(40)]
+        );
+    }
+
+    #[test]
+    fn respects_concurrency_limit() {
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+
+        run_concurrently((0..8).collect(), 3, |_: i32| {
+            let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max_in_flight.fetch_max(current, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(10));
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+        });
+
+        assert!(max_in_flight.load(Ordering::SeqCst) <= 3);
+    }
+}
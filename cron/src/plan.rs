@@ -0,0 +1,327 @@
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+use abstract_core::ans_host::ExecuteMsg;
+use abstract_core::objects::pool_id::UncheckedPoolAddress;
+use abstract_core::objects::{PoolMetadata, UncheckedContractEntry, UniquePoolId};
+use abstract_interface::{AbstractInterfaceError, AnsHost};
+use cw_asset::AssetInfoBase;
+use cw_orch::prelude::*;
+use cw_orch::state::ChainState;
+use serde::{Deserialize, Serialize};
+
+use crate::journal::{contract_key, pool_address_key};
+use crate::rollback::Snapshot;
+use crate::{AnsData, AnsDataDiff, EntityType};
+
+/// Changes to one entity, in a form that can be stored in a plan file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityChanges<R, A> {
+    pub to_remove: Vec<R>,
+    pub to_add: Vec<A>,
+}
+
+impl<R, A> EntityChanges<R, A> {
+    pub fn len(&self) -> usize {
+        self.to_remove.len() + self.to_add.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// An [`AnsDataDiff`] with its entries sorted, so the same diff always renders and hashes the same
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlannedDiff {
+    pub contracts: EntityChanges<UncheckedContractEntry, (UncheckedContractEntry, String)>,
+    pub assets: EntityChanges<String, (String, AssetInfoBase<String>)>,
+    pub dexes: EntityChanges<String, String>,
+    pub pools: EntityChanges<UniquePoolId, (UncheckedPoolAddress, PoolMetadata)>,
+}
+
+impl From<&AnsDataDiff> for PlannedDiff {
+    fn from(diff: &AnsDataDiff) -> Self {
+        let mut contracts = EntityChanges {
+            to_remove: diff.contracts.0.iter().cloned().collect::<Vec<_>>(),
+            to_add: diff.contracts.1.clone().into_iter().collect::<Vec<_>>(),
+        };
+        contracts.to_remove.sort_by_key(contract_key);
+        contracts.to_add.sort_by_key(|(entry, _)| contract_key(entry));
+
+        let mut assets = EntityChanges {
+            to_remove: diff.assets.0.iter().cloned().collect::<Vec<_>>(),
+            to_add: diff.assets.1.clone().into_iter().collect::<Vec<_>>(),
+        };
+        assets.to_remove.sort();
+        assets.to_add.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut dexes = EntityChanges {
+            to_remove: diff.dexes.0.iter().cloned().collect::<Vec<_>>(),
+            to_add: diff.dexes.1.keys().cloned().collect::<Vec<_>>(),
+        };
+        dexes.to_remove.sort();
+        dexes.to_add.sort();
+
+        let mut pools = EntityChanges {
+            to_remove: diff.pools.0.iter().copied().collect::<Vec<_>>(),
+            to_add: diff.pools.1.clone().into_iter().collect::<Vec<_>>(),
+        };
+        pools.to_remove.sort_by_key(|id| id.to_string());
+        pools.to_add.sort_by_key(|(address, _)| pool_address_key(address));
+
+        Self {
+            contracts,
+            assets,
+            dexes,
+            pools,
+        }
+    }
+}
+
+impl From<&PlannedDiff> for AnsDataDiff {
+    fn from(diff: &PlannedDiff) -> Self {
+        Self {
+            contracts: (
+                diff.contracts.to_remove.iter().cloned().collect(),
+                diff.contracts.to_add.iter().cloned().collect(),
+            ),
+            assets: (
+                diff.assets.to_remove.iter().cloned().collect(),
+                diff.assets.to_add.iter().cloned().collect(),
+            ),
+            dexes: (
+                diff.dexes.to_remove.iter().cloned().collect(),
+                diff.dexes
+                    .to_add
+                    .iter()
+                    .map(|dex| (dex.clone(), dex.clone()))
+                    .collect(),
+            ),
+            pools: (
+                diff.pools.to_remove.iter().copied().collect(),
+                diff.pools.to_add.iter().cloned().collect(),
+            ),
+        }
+    }
+}
+
+impl PlannedDiff {
+    pub fn len(&self) -> usize {
+        self.contracts.len() + self.assets.len() + self.dexes.len() + self.pools.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Display for PlannedDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.contracts.to_remove {
+            writeln!(f, "  - contract {}", contract_key(entry))?;
+        }
+        for (entry, address) in &self.contracts.to_add {
+            writeln!(f, "  + contract {} = {}", contract_key(entry), address)?;
+        }
+        for name in &self.assets.to_remove {
+            writeln!(f, "  - asset {}", name)?;
+        }
+        for (name, info) in &self.assets.to_add {
+            writeln!(f, "  + asset {} = {:?}", name, info)?;
+        }
+        for dex in &self.dexes.to_remove {
+            writeln!(f, "  - dex {}", dex)?;
+        }
+        for dex in &self.dexes.to_add {
+            writeln!(f, "  + dex {}", dex)?;
+        }
+        for id in &self.pools.to_remove {
+            writeln!(f, "  - pool {}", id)?;
+        }
+        for (address, metadata) in &self.pools.to_add {
+            writeln!(f, "  + pool {} = {:?}", pool_address_key(address), metadata)?;
+        }
+        Ok(())
+    }
+}
+
+/// Everything that will be executed on one chain, and the state it was computed from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainPlan {
+    pub chain_id: String,
+    /// Entity types the plan syncs
+    pub entities: Vec<EntityType>,
+    /// Block height at which the on-chain entries were queried
+    pub height: u64,
+    /// sha256 of the scraped entries the diff was computed from
+    pub scraped_hash: String,
+    /// sha256 of the on-chain entries, the plan can't be applied once they changed
+    pub on_chain_hash: String,
+    pub diff: PlannedDiff,
+    /// The exact ANS host messages, in execution order
+    pub msgs: Vec<ExecuteMsg>,
+}
+
+impl fmt::Display for ChainPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} at height {}: {} changes in {} messages",
+            self.chain_id,
+            self.height,
+            self.diff.len(),
+            self.msgs.len()
+        )?;
+        write!(f, "{}", self.diff)
+    }
+}
+
+/// A reviewed set of chain plans, identified by the hash of its content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Plan {
+    /// sha256 of the serialized chain plans
+    pub hash: String,
+    pub chains: Vec<ChainPlan>,
+}
+
+impl Plan {
+    pub fn new(chains: Vec<ChainPlan>) -> Result<Self, AbstractInterfaceError> {
+        Ok(Self {
+            hash: sha256::digest(serde_json::to_string(&chains)?),
+            chains,
+        })
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), AbstractInterfaceError> {
+        let file = File::create(path).map_err(|e| CwOrchError::StdErr(e.to_string()))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// Read a plan file and check that it wasn't modified since it was made
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, AbstractInterfaceError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| {
+            CwOrchError::StdErr(format!("plan not found at {}: {e}", path.display()))
+        })?;
+        let plan: Self = serde_json::from_reader(file)?;
+        let hash = sha256::digest(serde_json::to_string(&plan.chains)?);
+        if hash != plan.hash {
+            return Err(CwOrchError::StdErr(format!(
+                "plan {} was modified, its content hashes to {hash}",
+                plan.hash
+            ))
+            .into());
+        }
+        Ok(plan)
+    }
+}
+
+/// sha256 of a set of ANS entries
+pub fn entries_hash(
+    chain_id: &str,
+    entries: &AnsData,
+    entities: &[EntityType],
+) -> Result<String, AbstractInterfaceError> {
+    Snapshot::new(chain_id, entries, entities).content_hash()
+}
+
+/// Compute the plan that brings the ANS host from `on_chain_entries` to `scraped_entries`
+pub fn plan_chain(
+    ans_host: &AnsHost<Daemon>,
+    scraped_entries: AnsData,
+    on_chain_entries: AnsData,
+    entities: &[EntityType],
+) -> Result<ChainPlan, AbstractInterfaceError> {
+    let chain = ans_host.get_chain();
+    let chain_id = chain.state().chain_data.chain_id.to_string();
+    let height = chain
+        .block_info()
+        .map_err(|e| CwOrchError::StdErr(e.to_string()))?
+        .height;
+
+    let scraped_hash = entries_hash(&chain_id, &scraped_entries, entities)?;
+    let on_chain_hash = entries_hash(&chain_id, &on_chain_entries, entities)?;
+    let diff = crate::diff(scraped_entries, on_chain_entries)?;
+    let planned_diff = PlannedDiff::from(&diff);
+
+    Ok(ChainPlan {
+        chain_id,
+        entities: entities.to_vec(),
+        height,
+        scraped_hash,
+        on_chain_hash,
+        diff: planned_diff,
+        msgs: crate::update_msgs(diff),
+    })
+}
+
+/// Refuse to apply `plan` when the ANS host changed since the plan was made
+pub fn assert_on_chain_unchanged(
+    plan: &ChainPlan,
+    on_chain_entries: &AnsData,
+) -> Result<(), AbstractInterfaceError> {
+    let on_chain_hash = entries_hash(&plan.chain_id, on_chain_entries, &plan.entities)?;
+    if on_chain_hash != plan.on_chain_hash {
+        return Err(CwOrchError::StdErr(format!(
+            "ANS host on {} changed since the plan was made at height {}, make a new plan",
+            plan.chain_id, plan.height
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assets(names: &[&str]) -> AnsData {
+        let mut data = AnsData::default();
+        for name in names {
+            data.assets
+                .insert(name.to_string(), AssetInfoBase::Native(name.to_string()));
+        }
+        data
+    }
+
+    #[test]
+    fn same_entries_same_hash() {
+        let entities = crate::ANS_DATA_ENTITIES;
+        let a = entries_hash("juno-1", &assets(&["a", "b", "c", "d"]), entities).unwrap();
+        let b = entries_hash("juno-1", &assets(&["d", "c", "b", "a"]), entities).unwrap();
+        let c = entries_hash("juno-1", &assets(&["a", "b", "c"]), entities).unwrap();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn planned_diff_is_sorted() {
+        let diff = crate::diff(assets(&["c", "a", "b"]), AnsData::default()).unwrap();
+
+        let planned = PlannedDiff::from(&diff);
+
+        let names: Vec<_> = planned
+            .assets
+            .to_add
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+        assert_eq!(planned.len(), 3);
+    }
+
+    #[test]
+    fn planned_diff_converts_back() {
+        let diff = crate::diff(assets(&["a", "b"]), assets(&["b", "c"])).unwrap();
+        let planned = PlannedDiff::from(&diff);
+
+        let converted = AnsDataDiff::from(&planned);
+
+        assert_eq!(converted.assets, diff.assets);
+        assert_eq!(PlannedDiff::from(&converted), planned);
+    }
+}
@@ -8,7 +8,7 @@ use cw_asset::AssetInfoBase;
 use cw_orch::prelude::*;
 use serde_json::Value;
 
-use crate::{batch_execute_ans, EntryDif};
+use crate::{batch_ans_msgs, EntryDif, ScrapedFiles};
 
 pub type ScrapedEntries = (
     HashMap<PoolAddressBase<String>, PoolMetadata>,
@@ -17,12 +17,16 @@ pub type ScrapedEntries = (
 );
 
 pub fn get_scraped_entries(
+    files: &ScrapedFiles,
     chain_name: &String,
     chain_id: &String,
 ) -> Result<ScrapedEntries, AbstractInterfaceError> {
-    let raw_scraped_entries = crate::get_scraped_json_data("pools");
+    let raw_scraped_entries = files.json("pools")?;
     let binding = raw_scraped_entries[chain_name][chain_id].clone();
-    let parsed_scraped_entries: &Vec<Value> = binding.as_array().unwrap();
+    let parsed_scraped_entries: &Vec<Value> = binding.as_array().ok_or_else(|| {
+        CwOrchError::StdErr(format!("no pools found for {chain_name} {chain_id}"))
+    })?;
+    let dexes = &crate::config::get().dexes;
     let mut dexes_to_register: HashSet<String> = HashSet::new();
     let mut lp_assets: HashMap<String, AssetInfoBase<String>> = HashMap::new();
 
@@ -30,9 +34,15 @@ pub fn get_scraped_entries(
         .iter()
         .map(|value| {
             let pool: (UncheckedPoolAddress, PoolMetadata) =
-                serde_json::from_value(value.clone()).unwrap();
-            // If pool uses pool-id we assume it is on Osmosis and register the LP assets with the `gamm/pool/{pool_id}` prefix.
-            if let UncheckedPoolAddress::Id(pool_id) = pool.0 {
+                serde_json::from_value(value.clone())?;
+            // The LP token of a pool addressed by id is a native denom, templated per dex in the config
+            let lp_denom = match pool.0 {
+                UncheckedPoolAddress::Id(pool_id) => dexes
+                    .get(&pool.1.dex)
+                    .and_then(|rules| rules.lp_denom(pool_id)),
+                _ => None,
+            };
+            if let Some(lp_denom) = lp_denom {
                 let dex = &pool.1.dex;
                 let assets = pool
                     .1
@@ -42,16 +52,13 @@ pub fn get_scraped_entries(
                     .collect::<Vec<_>>()
                     .join(",");
                 let asset_name = format!("{dex}/{assets}",);
-                lp_assets.insert(
-                    asset_name,
-                    AssetInfoBase::Native(format!("gamm/pool/{pool_id}")),
-                );
+                lp_assets.insert(asset_name, AssetInfoBase::Native(lp_denom));
             }
             dexes_to_register.insert(pool.1.dex.clone());
 
-            pool
+            Ok(pool)
         })
-        .collect();
+        .collect::<Result<_, AbstractInterfaceError>>()?;
 
     Ok((
         scraped_entries_vec.into_iter().collect(),
@@ -84,11 +91,13 @@ pub fn get_on_chain_entries(
             )?
             .pools;
 
+        // A pairing lists every pool of its assets, only the one with this id has this metadata
         let metadata_to_save: HashMap<_, _> = metadatas
             .iter()
             .zip(addresses.iter())
             .flat_map(|(m, a)| {
                 a.1.iter()
+                    .filter(|a| a.unique_id == m.0)
                     .map(|a| (a.pool_address.clone().into(), m.clone()))
             })
             .collect();
@@ -100,6 +109,47 @@ pub fn get_on_chain_entries(
     Ok(on_chain_entries)
 }
 
+/// All pool metadata registered on the ANS host, including metadata that has no pool address
+pub fn get_on_chain_metadatas(
+    ans_host: &AnsHost<Daemon>,
+) -> Result<Vec<(UniquePoolId, PoolMetadata)>, AbstractInterfaceError> {
+    let mut on_chain_metadatas = vec![];
+    let mut last_pool = None;
+    loop {
+        let PoolMetadataListResponse { metadatas } =
+            ans_host.pool_metadata_list(None, Some(100), last_pool)?;
+        if metadatas.is_empty() {
+            break;
+        }
+        last_pool = metadatas.last().map(|l| l.0);
+        on_chain_metadatas.extend(metadatas);
+    }
+
+    Ok(on_chain_metadatas)
+}
+
+/// Address and unique id of every pool registered under the pairings of `metadatas`
+pub fn get_on_chain_pool_references(
+    ans_host: &AnsHost<Daemon>,
+    metadatas: &[(UniquePoolId, PoolMetadata)],
+) -> Result<Vec<(UncheckedPoolAddress, UniquePoolId)>, AbstractInterfaceError> {
+    let mut references = vec![];
+    for chunk in metadatas.chunks(100) {
+        let pairings = chunk
+            .iter()
+            .map(|(_, m)| DexAssetPairing::new(m.assets[0].clone(), m.assets[1].clone(), &m.dex))
+            .collect();
+        for (_, pool_references) in ans_host.pools(pairings)?.pools {
+            references.extend(
+                pool_references
+                    .into_iter()
+                    .map(|reference| (reference.pool_address.into(), reference.unique_id)),
+            );
+        }
+    }
+    Ok(references)
+}
+
 pub fn get_on_chain_dexes(
     ans_host: &AnsHost<Daemon>,
 ) -> Result<Vec<String>, AbstractInterfaceError> {
@@ -107,53 +157,47 @@ pub fn get_on_chain_dexes(
     Ok(dexes)
 }
 
-pub fn update(
-    ans_host: &AnsHost<Daemon>,
+pub fn update_msgs(
     diff: (
         HashSet<UniquePoolId>,
         HashMap<UncheckedPoolAddress, PoolMetadata>,
     ),
-) -> Result<(), AbstractInterfaceError> {
+) -> Vec<ExecuteMsg> {
     let to_add: Vec<_> = diff.1.into_iter().collect();
     let to_remove: Vec<_> = diff.0.into_iter().collect();
+    let batch_size = crate::config::get().batch_sizes.pools.get();
 
     // add the pools
-    batch_execute_ans(&ans_host, &to_add.into_iter().collect::<Vec<_>>(), 25, |chunk| {
-        ExecuteMsg::UpdatePools {
-            to_add: chunk.to_vec(),
-            to_remove: vec![],
-        }
-    })?;
+    let mut msgs = batch_ans_msgs(&to_add, batch_size, |chunk| ExecuteMsg::UpdatePools {
+        to_add: chunk.to_vec(),
+        to_remove: vec![],
+    });
 
     // remove the pools
-    batch_execute_ans(&ans_host, &to_remove.into_iter().collect::<Vec<_>>(), 25, |chunk| {
-        ExecuteMsg::UpdatePools {
-            to_add: vec![],
-            to_remove: chunk.to_vec(),
-        }
-    })?;
+    msgs.extend(batch_ans_msgs(&to_remove, batch_size, |chunk| ExecuteMsg::UpdatePools {
+        to_add: vec![],
+        to_remove: chunk.to_vec(),
+    }));
 
-    Ok(())
+    msgs
 }
 
-pub fn update_dexes(
-    ans_host: &AnsHost<Daemon>,
-    diff: EntryDif<String, String>,
-) -> Result<(), AbstractInterfaceError> {
+pub fn update_dexes_msgs(diff: EntryDif<String, String>) -> Vec<ExecuteMsg> {
     let to_add: Vec<_> = diff.1.into_keys().collect();
     let to_remove: Vec<_> = diff.0.into_iter().collect();
+    let batch_size = crate::config::get().batch_sizes.dexes.get();
 
     // add the dexes
-    batch_execute_ans(ans_host, &to_add, 25, |chunk| ExecuteMsg::UpdateDexes {
+    let mut msgs = batch_ans_msgs(&to_add, batch_size, |chunk| ExecuteMsg::UpdateDexes {
         to_add: chunk.to_vec(),
         to_remove: vec![],
-    })?;
+    });
 
     // remove the dexes
-    batch_execute_ans(ans_host, &to_remove, 25, |chunk| ExecuteMsg::UpdateDexes {
+    msgs.extend(batch_ans_msgs(&to_remove, batch_size, |chunk| ExecuteMsg::UpdateDexes {
         to_add: vec![],
         to_remove: chunk.to_vec(),
-    })?;
+    }));
 
-    Ok(())
+    msgs
 }
@@ -0,0 +1,357 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::Path;
+
+use abstract_core::objects::pool_id::UncheckedPoolAddress;
+use abstract_core::objects::{PoolMetadata, UncheckedContractEntry, UniquePoolId};
+use abstract_interface::AbstractInterfaceError;
+use cw_asset::AssetInfoBase;
+use cw_orch::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::journal::{contract_key, pool_address_key, JournalRecord, MsgKind};
+use crate::{AnsData, AnsDataDiff, EntityType};
+
+/// On-chain ANS state of a chain, stored before an update so it can be restored
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub chain_id: String,
+    /// Entity types the snapshot holds, the others weren't queried
+    pub entities: Vec<EntityType>,
+    pub contracts: Vec<(UncheckedContractEntry, String)>,
+    pub assets: Vec<(String, AssetInfoBase<String>)>,
+    pub dexes: Vec<String>,
+    pub pools: Vec<(UncheckedPoolAddress, UniquePoolId, PoolMetadata)>,
+}
+
+impl Snapshot {
+    /// Entries are sorted on their key so the same state always gives the same snapshot
+    pub fn new(chain_id: &str, entries: &AnsData, entities: &[EntityType]) -> Self {
+        let mut contracts: Vec<_> = entries.contracts.clone().into_iter().collect();
+        contracts.sort_by_key(|(entry, _)| contract_key(entry));
+        let mut assets: Vec<_> = entries.assets.clone().into_iter().collect();
+        assets.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let mut dexes: Vec<_> = entries.dexes.keys().cloned().collect();
+        dexes.sort();
+        let mut pools: Vec<_> = entries
+            .pools
+            .iter()
+            .map(|(address, (id, metadata))| (address.clone(), *id, metadata.clone()))
+            .collect();
+        pools.sort_by_key(|(address, _, _)| pool_address_key(address));
+
+        Self {
+            chain_id: chain_id.to_string(),
+            entities: entities.to_vec(),
+            contracts,
+            assets,
+            dexes,
+            pools,
+        }
+    }
+
+    /// sha256 of the snapshot, identifies the ANS state it was taken from
+    pub fn content_hash(&self) -> Result<String, AbstractInterfaceError> {
+        Ok(sha256::digest(serde_json::to_string(self)?))
+    }
+
+    pub fn into_ans_data(self) -> AnsData {
+        AnsData {
+            contracts: self.contracts.into_iter().collect(),
+            assets: self.assets.into_iter().collect(),
+            dexes: self.dexes.into_iter().map(|v| (v.clone(), v)).collect(),
+            pools: self
+                .pools
+                .into_iter()
+                .map(|(address, id, metadata)| (address, (id, metadata)))
+                .collect(),
+        }
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), AbstractInterfaceError> {
+        let file = File::create(path).map_err(|e| CwOrchError::StdErr(e.to_string()))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, AbstractInterfaceError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| {
+            CwOrchError::StdErr(format!("snapshot not found at {}: {e}", path.display()))
+        })?;
+        Ok(serde_json::from_reader(file)?)
+    }
+}
+
+/// Entries a run touched on one chain, by entity
+#[derive(Debug, Default)]
+pub struct TouchedKeys {
+    pub contracts: HashSet<String>,
+    pub assets: HashSet<String>,
+    pub dexes: HashSet<String>,
+    /// Addresses of the pools the run added
+    pub added_pool_addresses: HashSet<String>,
+    /// Ids of the pools the run removed, an `Id` pool address has the same form so they're kept apart
+    pub removed_pool_ids: HashSet<String>,
+}
+
+impl TouchedKeys {
+    pub fn from_records<'a>(records: impl IntoIterator<Item = &'a JournalRecord>) -> Self {
+        let mut touched = Self::default();
+        for record in records {
+            let keys = match record.entity {
+                EntityType::Contracts => &mut touched.contracts,
+                EntityType::Assets => &mut touched.assets,
+                EntityType::Dexes => &mut touched.dexes,
+                EntityType::Pools => match record.kind {
+                    MsgKind::Add => &mut touched.added_pool_addresses,
+                    MsgKind::Remove => &mut touched.removed_pool_ids,
+                },
+                // Channels are never removed by the updater, nothing to restore
+                EntityType::Channels => continue,
+            };
+            keys.extend(record.keys.iter().cloned());
+        }
+        touched
+    }
+}
+
+/// The diff that brings the ANS host from `on_chain_entries` back to `snapshot`.
+/// When `touched` is given, only the entries the run touched are restored.
+pub fn rollback_diff(
+    snapshot: AnsData,
+    on_chain_entries: AnsData,
+    touched: Option<&TouchedKeys>,
+) -> Result<AnsDataDiff, AbstractInterfaceError> {
+    // Pool removals are journaled by id, map them back to the address they had before the update
+    let removed_pool_addresses: HashSet<String> = touched
+        .map(|touched| {
+            snapshot
+                .pools
+                .iter()
+                .filter(|(_, (id, _))| touched.removed_pool_ids.contains(&id.to_string()))
+                .map(|(address, _)| pool_address_key(address))
+                .collect()
+        })
+        .unwrap_or_default();
+    // A pool whose metadata the run changed is journaled as added, its old metadata is restored
+    let changed_pool_addresses: HashSet<String> = touched
+        .map(|touched| {
+            snapshot
+                .pools
+                .keys()
+                .map(pool_address_key)
+                .filter(|address| touched.added_pool_addresses.contains(address))
+                .collect()
+        })
+        .unwrap_or_default();
+    let on_chain_pool_ids: Vec<(UniquePoolId, String)> = on_chain_entries
+        .pools
+        .iter()
+        .map(|(address, (id, _))| (*id, pool_address_key(address)))
+        .collect();
+
+    let mut diff = crate::diff(snapshot, on_chain_entries)?;
+
+    let Some(touched) = touched else {
+        return Ok(diff);
+    };
+
+    diff.contracts
+        .0
+        .retain(|entry| touched.contracts.contains(&contract_key(entry)));
+    diff.contracts
+        .1
+        .retain(|entry, _| touched.contracts.contains(&contract_key(entry)));
+    diff.assets.0.retain(|name| touched.assets.contains(name));
+    diff.assets
+        .1
+        .retain(|name, _| touched.assets.contains(name));
+    diff.dexes.0.retain(|dex| touched.dexes.contains(dex));
+    diff.dexes.1.retain(|dex, _| touched.dexes.contains(dex));
+    // Pools the run added are removed again, pools it removed or changed are added back
+    diff.pools.0.retain(|id| {
+        on_chain_pool_ids.iter().any(|(on_chain_id, address)| {
+            on_chain_id == id && touched.added_pool_addresses.contains(address)
+        })
+    });
+    diff.pools.1.retain(|address, _| {
+        let address = pool_address_key(address);
+        removed_pool_addresses.contains(&address) || changed_pool_addresses.contains(&address)
+    });
+
+    Ok(diff)
+}
+
+/// Journal records of `chain_id`
+pub fn chain_records<'a>(
+    records: &'a [JournalRecord],
+    chain_id: &'a str,
+) -> impl Iterator<Item = &'a JournalRecord> {
+    records
+        .iter()
+        .filter(move |record| record.chain_id == chain_id)
+}
+
+/// Number of entries the records added and removed, for logging before a rollback
+pub fn count_records<'a>(records: impl IntoIterator<Item = &'a JournalRecord>) -> (usize, usize) {
+    records
+        .into_iter()
+        .fold((0, 0), |(added, removed), record| match record.kind {
+            MsgKind::Add => (added + record.keys.len(), removed),
+            MsgKind::Remove => (added, removed + record.keys.len()),
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use abstract_core::ans_host::ExecuteMsg;
+    use abstract_core::objects::pool_id::PoolAddressBase;
+    use abstract_core::objects::{AssetEntry, PoolType};
+
+    use super::*;
+
+    fn record(kind: MsgKind, keys: &[&str]) -> JournalRecord {
+        entity_record(EntityType::Assets, kind, keys)
+    }
+
+    fn entity_record(entity: EntityType, kind: MsgKind, keys: &[&str]) -> JournalRecord {
+        JournalRecord {
+            chain_id: "juno-1".to_string(),
+            entity,
+            kind,
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+            execution: "direct".to_string(),
+            tx_hash: String::new(),
+            height: 0,
+            gas_wanted: 0,
+            gas_used: 0,
+            estimated_fee: String::new(),
+            timestamp: String::new(),
+            msg: ExecuteMsg::UpdateDexes {
+                to_add: vec![],
+                to_remove: vec![],
+            },
+        }
+    }
+
+    fn native(denom: &str) -> AssetInfoBase<String> {
+        AssetInfoBase::Native(denom.to_string())
+    }
+
+    #[test]
+    fn restores_only_touched_entries() {
+        let mut snapshot = AnsData::default();
+        snapshot.assets.insert("a".to_string(), native("a"));
+        snapshot.assets.insert("b".to_string(), native("b"));
+
+        let mut on_chain = AnsData::default();
+        // changed by the run
+        on_chain.assets.insert("a".to_string(), native("changed"));
+        // added by the run
+        on_chain.assets.insert("c".to_string(), native("c"));
+        // added by someone else after the run
+        on_chain.assets.insert("d".to_string(), native("d"));
+
+        let records = vec![
+            record(MsgKind::Add, &["a", "c"]),
+            record(MsgKind::Remove, &["b"]),
+        ];
+        let touched = TouchedKeys::from_records(&records);
+
+        let diff = rollback_diff(snapshot, on_chain, Some(&touched)).unwrap();
+
+        assert_eq!(diff.assets.0, HashSet::from(["c".to_string()]));
+        assert_eq!(diff.assets.1.len(), 2);
+        assert_eq!(diff.assets.1["a"], native("a"));
+        assert_eq!(diff.assets.1["b"], native("b"));
+    }
+
+    fn pool(id: u64, dex: &str) -> (UniquePoolId, PoolMetadata) {
+        (
+            UniquePoolId::new(id),
+            PoolMetadata {
+                dex: dex.to_string(),
+                pool_type: PoolType::ConstantProduct,
+                assets: vec![AssetEntry::new("a"), AssetEntry::new("b")],
+            },
+        )
+    }
+
+    #[test]
+    fn keeps_pool_ids_and_addresses_apart() {
+        let mut snapshot = AnsData::default();
+        snapshot
+            .pools
+            .insert(PoolAddressBase::Id(1), pool(7, "osmosis"));
+        snapshot
+            .pools
+            .insert(PoolAddressBase::Id(2), pool(8, "osmosis"));
+
+        let mut on_chain = AnsData::default();
+        // changed by someone else after the run, its old id is the address of the added pool
+        on_chain
+            .pools
+            .insert(PoolAddressBase::Id(1), pool(10, "other"));
+        // added by the run
+        on_chain
+            .pools
+            .insert(PoolAddressBase::Id(7), pool(9, "osmosis"));
+
+        let records = vec![
+            entity_record(EntityType::Pools, MsgKind::Remove, &["8"]),
+            entity_record(EntityType::Pools, MsgKind::Add, &["7"]),
+        ];
+        let touched = TouchedKeys::from_records(&records);
+
+        let diff = rollback_diff(snapshot, on_chain, Some(&touched)).unwrap();
+
+        assert_eq!(diff.pools.0, HashSet::from([UniquePoolId::new(9)]));
+        assert_eq!(diff.pools.1.len(), 1);
+        assert!(diff.pools.1.contains_key(&PoolAddressBase::Id(2)));
+    }
+
+    #[test]
+    fn restores_changed_pool_metadata() {
+        let mut snapshot = AnsData::default();
+        snapshot
+            .pools
+            .insert(PoolAddressBase::Id(1), pool(7, "osmosis"));
+        snapshot
+            .pools
+            .insert(PoolAddressBase::Id(2), pool(8, "osmosis"));
+
+        let mut on_chain = AnsData::default();
+        // metadata changed by the run
+        on_chain
+            .pools
+            .insert(PoolAddressBase::Id(1), pool(7, "other"));
+        // metadata changed by someone else after the run
+        on_chain
+            .pools
+            .insert(PoolAddressBase::Id(2), pool(8, "other"));
+
+        let records = vec![entity_record(EntityType::Pools, MsgKind::Add, &["1"])];
+        let touched = TouchedKeys::from_records(&records);
+
+        let diff = rollback_diff(snapshot, on_chain, Some(&touched)).unwrap();
+
+        assert!(diff.pools.0.is_empty());
+        assert_eq!(diff.pools.1.len(), 1);
+        assert_eq!(diff.pools.1[&PoolAddressBase::Id(1)], pool(7, "osmosis").1);
+    }
+
+    #[test]
+    fn restores_full_snapshot() {
+        let mut snapshot = AnsData::default();
+        snapshot.assets.insert("a".to_string(), native("a"));
+
+        let mut on_chain = AnsData::default();
+        on_chain.assets.insert("d".to_string(), native("d"));
+
+        let diff = rollback_diff(snapshot, on_chain, None).unwrap();
+
+        assert_eq!(diff.assets.0, HashSet::from(["d".to_string()]));
+        assert_eq!(diff.assets.1["a"], native("a"));
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt;
+
+use abstract_interface::AbstractInterfaceError;
+use cw_orch::prelude::*;
+
+use crate::{AnsData, AnsDataDiff, EntityType};
+
+/// Limits on what a single run may remove from the ANS host, per entity type and chain.
+/// A broken scrape shows up as a large removal, so we refuse to apply those by default.
+#[derive(Debug, Clone, Copy)]
+pub struct RemovalLimits {
+    /// Maximum number of entries that can be removed
+    pub max_removals: usize,
+    /// Maximum share of the on-chain entries that can be removed, in percent
+    pub max_removal_percent: f64,
+    /// Entity types with fewer on-chain entries only have the absolute limit,
+    /// a single removal from a handful of entries is already a large share
+    pub min_on_chain_for_percent: usize,
+}
+
+impl Default for RemovalLimits {
+    fn default() -> Self {
+        Self {
+            max_removals: 50,
+            max_removal_percent: 25.0,
+            min_on_chain_for_percent: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LimitViolation {
+    pub entity: EntityType,
+    pub removals: usize,
+    pub on_chain: usize,
+}
+
+impl LimitViolation {
+    pub fn percent(&self) -> f64 {
+        if self.on_chain == 0 {
+            return 0.0;
+        }
+        self.removals as f64 * 100.0 / self.on_chain as f64
+    }
+}
+
+impl fmt::Display for LimitViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?}: {} of {} on-chain entries would be removed ({:.1}%)",
+            self.entity,
+            self.removals,
+            self.on_chain,
+            self.percent()
+        )
+    }
+}
+
+/// The entity types of `diff` that remove more than `limits` allows
+pub fn check_removals(
+    diff: &AnsDataDiff,
+    on_chain_entries: &AnsData,
+    limits: &RemovalLimits,
+) -> Vec<LimitViolation> {
+    [
+        (
+            EntityType::Contracts,
+            diff.contracts.0.len(),
+            on_chain_entries.contracts.len(),
+        ),
+        (
+            EntityType::Assets,
+            diff.assets.0.len(),
+            on_chain_entries.assets.len(),
+        ),
+        (
+            EntityType::Dexes,
+            diff.dexes.0.len(),
+            on_chain_entries.dexes.len(),
+        ),
+        (
+            EntityType::Pools,
+            diff.pools.0.len(),
+            on_chain_entries.pools.len(),
+        ),
+    ]
+    .into_iter()
+    .map(|(entity, removals, on_chain)| LimitViolation {
+        entity,
+        removals,
+        on_chain,
+    })
+    .filter(|violation| {
+        violation.removals > limits.max_removals
+            || (violation.on_chain >= limits.min_on_chain_for_percent
+                && violation.percent() > limits.max_removal_percent)
+    })
+    .collect()
+}
+
+/// Error out before anything is broadcast when `diff` removes more than `limits` allows
+pub fn assert_removals(
+    chain_id: &str,
+    diff: &AnsDataDiff,
+    on_chain_entries: &AnsData,
+    limits: &RemovalLimits,
+) -> Result<(), AbstractInterfaceError> {
+    let violations = check_removals(diff, on_chain_entries, limits);
+    if violations.is_empty() {
+        return Ok(());
+    }
+    Err(CwOrchError::StdErr(format!(
+        "removal limits (max {} entries, or {}% of {} or more entries) exceeded on {}:\n{}",
+        limits.max_removals,
+        limits.max_removal_percent,
+        limits.min_on_chain_for_percent,
+        chain_id,
+        violations
+            .iter()
+            .map(|violation| format!("  {violation}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    ))
+    .into())
+}
+
+#[cfg(test)]
+mod test {
+    use cw_asset::AssetInfoBase;
+
+    use super::*;
+
+    fn assets(n: usize) -> AnsData {
+        let mut data = AnsData::default();
+        for i in 0..n {
+            data.assets
+                .insert(format!("asset{i}"), AssetInfoBase::Native(format!("u{i}")));
+        }
+        data
+    }
+
+    #[test]
+    fn empty_scrape_exceeds_limits() {
+        let on_chain = assets(200);
+        let diff = crate::diff(AnsData::default(), on_chain.clone()).unwrap();
+
+        let violations = check_removals(&diff, &on_chain, &RemovalLimits::default());
+
+        assert_eq!(
+            violations,
+            vec![LimitViolation {
+                entity: EntityType::Assets,
+                removals: 200,
+                on_chain: 200,
+            }]
+        );
+    }
+
+    #[test]
+    fn percentage_limit() {
+        let on_chain = assets(10);
+        let diff = crate::diff(assets(7), on_chain.clone()).unwrap();
+
+        let limits = RemovalLimits {
+            max_removals: 50,
+            max_removal_percent: 25.0,
+            min_on_chain_for_percent: 10,
+        };
+        assert_eq!(check_removals(&diff, &on_chain, &limits).len(), 1);
+
+        let limits = RemovalLimits {
+            max_removal_percent: 30.0,
+            ..limits
+        };
+        assert!(check_removals(&diff, &on_chain, &limits).is_empty());
+    }
+
+    #[test]
+    fn small_sets_only_have_the_absolute_limit() {
+        let on_chain = assets(4);
+        let diff = crate::diff(assets(2), on_chain.clone()).unwrap();
+
+        let limits = RemovalLimits::default();
+        assert!(check_removals(&diff, &on_chain, &limits).is_empty());
+
+        let limits = RemovalLimits {
+            max_removals: 1,
+            ..limits
+        };
+        assert_eq!(check_removals(&diff, &on_chain, &limits).len(), 1);
+    }
+}
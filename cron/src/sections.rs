@@ -0,0 +1,192 @@
+//! Resolution of the selected chains to the chain sections of the scraped files
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use abstract_interface::AbstractInterfaceError;
+use serde_json::Value;
+
+use crate::{EntityType, ScrapedFiles};
+
+/// The files a chain can have a section in
+pub const SCRAPED_FILES: &[&str] = &["contracts", "assets", "pools"];
+
+/// The scraped files the entries of `entity` are read from, the LP assets are in the pools file
+fn entity_files(entity: EntityType) -> &'static [&'static str] {
+    match entity {
+        EntityType::Contracts => &["contracts"],
+        EntityType::Assets => &["assets", "pools"],
+        EntityType::Dexes | EntityType::Pools => &["pools"],
+        EntityType::Channels => &[],
+    }
+}
+
+/// The scraped files the entries of `entities` are read from
+pub fn scraped_files(entities: &[EntityType]) -> Vec<&'static str> {
+    SCRAPED_FILES
+        .iter()
+        .copied()
+        .filter(|file| {
+            entities
+                .iter()
+                .any(|entity| entity_files(*entity).contains(file))
+        })
+        .collect()
+}
+
+/// Chain sections of a scraped file: the chain ids under every scraper chain name
+pub type Sections = BTreeMap<String, BTreeSet<String>>;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SectionReport {
+    /// Selected chains without a section, with several sections or with a section under another name,
+    /// as chain id and problem
+    pub errors: Vec<(String, String)>,
+    /// Sections of chain ids that aren't a known chain
+    pub unknown: Vec<String>,
+    /// Sections of known chains that no selected chain uses
+    pub unused: Vec<String>,
+}
+
+pub fn file_sections(raw_scraped_entries: &Value) -> Sections {
+    let Some(chains) = raw_scraped_entries.as_object() else {
+        return Sections::new();
+    };
+    chains
+        .iter()
+        .map(|(chain_name, sections)| {
+            let chain_ids = sections
+                .as_object()
+                .map(|sections| sections.keys().cloned().collect())
+                .unwrap_or_default();
+            (chain_name.clone(), chain_ids)
+        })
+        .collect()
+}
+
+/// Check that every `(scraped chain name, chain id)` of `selected` has exactly one section in each of `files`.
+/// `is_known` tells whether a chain id is a chain the updater can connect to.
+pub fn check_sections(
+    files: &[(&str, Sections)],
+    selected: &[(String, String)],
+    is_known: impl Fn(&str) -> bool,
+) -> SectionReport {
+    let mut report = SectionReport::default();
+    for (file, sections) in files {
+        for (chain_name, chain_id) in selected {
+            let listed_under: Vec<&str> = sections
+                .iter()
+                .filter(|(_, chain_ids)| chain_ids.contains(chain_id))
+                .map(|(name, _)| name.as_str())
+                .collect();
+            let error = match listed_under.as_slice() {
+                [name] if *name == chain_name.as_str() => continue,
+                [] => format!("{file}.json has no section for {chain_name}/{chain_id}"),
+                names if !names.contains(&chain_name.as_str()) => format!(
+                    "{file}.json lists {chain_id} under {} instead of {chain_name}, add an alias to `chain_aliases`",
+                    names.join(", ")
+                ),
+                names => format!(
+                    "{file}.json lists {chain_id} under several chains: {}",
+                    names.join(", ")
+                ),
+            };
+            report.errors.push((chain_id.clone(), error));
+        }
+
+        for (chain_name, chain_ids) in sections {
+            for chain_id in chain_ids {
+                let section = format!("{file}.json {chain_name}/{chain_id}");
+                if !is_known(chain_id) {
+                    report.unknown.push(section);
+                } else if !selected.contains(&(chain_name.clone(), chain_id.clone())) {
+                    report.unused.push(section);
+                }
+            }
+        }
+    }
+    report
+}
+
+/// Check the fetched scraped files of `entities` against the `selected` chains
+pub fn check_scraped_sections(
+    files: &ScrapedFiles,
+    selected: &[(String, String)],
+    entities: &[EntityType],
+) -> Result<SectionReport, AbstractInterfaceError> {
+    let config = crate::config::get();
+    let sections = scraped_files(entities)
+        .iter()
+        .map(|file| Ok((*file, file_sections(files.json(file)?))))
+        .collect::<Result<Vec<_>, AbstractInterfaceError>>()?;
+    Ok(check_sections(&sections, selected, |chain_id| {
+        config.network(chain_id).is_ok()
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn selected(chains: &[(&str, &str)]) -> Vec<(String, String)> {
+        chains
+            .iter()
+            .map(|(name, id)| (name.to_string(), id.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn resolves_selected_chains() {
+        let sections = file_sections(&json!({
+            "juno": {"juno-1": [], "uni-5": []},
+            "terra2": {"phoenix-1": []},
+        }));
+
+        let report = check_sections(
+            &[("assets", sections)],
+            &selected(&[("juno", "juno-1")]),
+            |chain_id| chain_id != "uni-5",
+        );
+
+        assert!(report.errors.is_empty());
+        assert_eq!(report.unknown, vec!["assets.json juno/uni-5"]);
+        assert_eq!(report.unused, vec!["assets.json terra2/phoenix-1"]);
+    }
+
+    #[test]
+    fn reports_unresolved_chains() {
+        let sections = file_sections(&json!({
+            "terra2": {"phoenix-1": []},
+            "sei": {"pacific-1": []},
+            "sei2": {"pacific-1": []},
+        }));
+
+        let report = check_sections(
+            &[("pools", sections)],
+            &selected(&[
+                ("terra", "phoenix-1"),
+                ("sei", "pacific-1"),
+                ("juno", "juno-1"),
+            ]),
+            |_| true,
+        );
+
+        assert_eq!(report.errors.len(), 3);
+        assert_eq!(report.errors[0].0, "phoenix-1");
+        assert!(report.errors[0].1.contains("under terra2 instead of terra"));
+        assert!(report.errors[1].1.contains("several chains: sei, sei2"));
+        assert_eq!(report.errors[2].0, "juno-1");
+        assert!(report.errors[2].1.contains("no section for juno/juno-1"));
+    }
+
+    #[test]
+    fn only_needs_the_files_of_the_entities() {
+        assert_eq!(scraped_files(&[EntityType::Contracts]), vec!["contracts"]);
+        assert_eq!(
+            scraped_files(&[EntityType::Assets]),
+            vec!["assets", "pools"]
+        );
+        assert!(scraped_files(&[EntityType::Channels]).is_empty());
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use abstract_interface::{AbstractInterfaceError, AnsHost};
+use cw_orch::daemon::ChainRegistryData as ChainData;
+use cw_orch::prelude::*;
+use cw_orch::state::ChainState;
+use serde::{Deserialize, Serialize};
+
+use crate::{AnsData, EntityType, ScrapedFiles};
+
+pub mod address;
+pub mod consistency;
+pub mod duplicates;
+pub mod existence;
+pub mod ibc;
+pub mod integrity;
+pub mod naming;
+pub mod pools;
+pub mod report;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem found in the scraped data before it is pushed on-chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Name of the violated rule, e.g. `asset-name-case`
+    pub rule: String,
+    pub entity: EntityType,
+    /// Key of the offending entry
+    pub key: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(rule: &str, entity: EntityType, key: &str, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            rule: rule.to_string(),
+            entity,
+            key: key.to_string(),
+            message,
+        }
+    }
+
+    pub fn warning(rule: &str, entity: EntityType, key: &str, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(rule, entity, key, message)
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(
+            f,
+            "{}[{}] {:?} `{}`: {}",
+            severity, self.rule, self.entity, self.key, self.message
+        )
+    }
+}
+
+/// Run every offline rule over the scraped entries of the chain of `ans_host`.
+/// Only the entity types in `entities` are synced, the others are checked against their on-chain entries.
+/// Duplicate entries are errors with `strict_duplicates` and warnings otherwise.
+pub fn validate(
+    ans_host: &AnsHost<Daemon>,
+    files: &ScrapedFiles,
+    scraped_entries: &AnsData,
+    entities: &[EntityType],
+    strict_duplicates: bool,
+) -> Result<Vec<Diagnostic>, AbstractInterfaceError> {
+    let chain_data = &ans_host.get_chain().state().chain_data;
+    let mut diagnostics = validate_data(
+        chain_data,
+        files,
+        scraped_entries,
+        entities,
+        strict_duplicates,
+    )?;
+    if !scraped_entries.pools.is_empty() || !scraped_entries.contracts.is_empty() {
+        let registered = registered_entries(ans_host, scraped_entries, entities)?;
+        diagnostics.extend(integrity::validate_references(scraped_entries, &registered));
+    }
+    Ok(diagnostics)
+}
+
+/// The rules of [`validate`] that only need the scraped data, every rule but the references
+/// to entries that are only on-chain
+pub fn validate_data(
+    chain_data: &ChainData,
+    files: &ScrapedFiles,
+    scraped_entries: &AnsData,
+    entities: &[EntityType],
+    strict_duplicates: bool,
+) -> Result<Vec<Diagnostic>, AbstractInterfaceError> {
+    let config = crate::config::get();
+    let known_chains: HashSet<String> = config.known_chains.iter().cloned().collect();
+
+    let duplicate_severity = if strict_duplicates {
+        Severity::Error
+    } else {
+        Severity::Warning
+    };
+    let chain_name = config.scraped_chain_name(&chain_data.chain_name);
+    let chain_id = chain_data.chain_id.to_string();
+    let mut diagnostics = vec![];
+    for entity in [EntityType::Contracts, EntityType::Assets, EntityType::Pools] {
+        if !entities.contains(&entity) {
+            continue;
+        }
+        let raw_scraped_entries = files.json(scraped_file(entity))?;
+        let section = &raw_scraped_entries[&chain_name][&chain_id];
+        let entries = duplicates::raw_entries(entity, section)?;
+        // Contracts like staking generators are shared by many entries, only asset values have to be unique
+        let check_values = entity == EntityType::Assets;
+        diagnostics.extend(duplicates::find_duplicates(
+            entity,
+            &entries,
+            check_values,
+            duplicate_severity,
+        ));
+    }
+
+    diagnostics.extend(naming::validate_asset_names(
+        &scraped_entries.assets,
+        &known_chains,
+    ));
+    diagnostics.extend(address::validate_addresses(
+        scraped_entries,
+        &chain_data.bech32_prefix,
+    ));
+    if !scraped_entries.assets.is_empty() {
+        let channels = ibc::parse_channels(files.text("channels")?)?;
+        let chain_assets = scraped_chain_assets(files, &chain_data.network_type)?;
+        diagnostics.extend(ibc::validate_ibc_denoms(
+            &chain_name,
+            &scraped_entries.assets,
+            &chain_assets,
+            &channels,
+        ));
+    }
+    diagnostics.extend(pools::validate_pools(&scraped_entries.pools, &config.dexes));
+    Ok(diagnostics)
+}
+
+/// Name of the scraped file that holds the entries of `entity`
+pub fn scraped_file(entity: EntityType) -> &'static str {
+    match entity {
+        EntityType::Contracts => "contracts",
+        EntityType::Assets => "assets",
+        EntityType::Dexes | EntityType::Pools => "pools",
+        EntityType::Channels => "channels",
+    }
+}
+
+/// The entries the ANS host holds after the update: the scraped ones for the synced entity types
+/// and the on-chain ones for the others
+fn registered_entries(
+    ans_host: &AnsHost<Daemon>,
+    scraped_entries: &AnsData,
+    entities: &[EntityType],
+) -> Result<AnsData, AbstractInterfaceError> {
+    let unsynced: Vec<EntityType> = crate::ANS_DATA_ENTITIES
+        .iter()
+        .filter(|entity| !entities.contains(entity))
+        .copied()
+        .collect();
+    let mut registered = crate::get_on_chain_entries(ans_host, &unsynced)?;
+    let synced = |entity| entities.contains(&entity);
+    if synced(EntityType::Contracts) {
+        registered.contracts = scraped_entries.contracts.clone();
+    }
+    if synced(EntityType::Assets) {
+        registered.assets = scraped_entries.assets.clone();
+    }
+    if synced(EntityType::Dexes) {
+        registered.dexes = scraped_entries.dexes.clone();
+    }
+    if synced(EntityType::Pools) {
+        registered.pools = scraped_entries.pools.clone();
+    }
+    Ok(registered)
+}
+
+/// Scraped assets of every chain of the same network type, mainnet or testnet
+pub fn scraped_chain_assets(
+    files: &ScrapedFiles,
+    network_type: &str,
+) -> Result<ibc::ChainAssets, AbstractInterfaceError> {
+    let raw_scraped_entries = files.json("assets")?;
+    let Some(chains) = raw_scraped_entries.as_object() else {
+        return Ok(Default::default());
+    };
+
+    let mut chain_assets = ibc::ChainAssets::new();
+    for (chain_name, sections) in chains {
+        let Some(sections) = sections.as_object() else {
+            continue;
+        };
+        for (chain_id, section) in sections {
+            let Ok(network) = crate::config::get().network(chain_id) else {
+                continue;
+            };
+            if ChainData::from(network).network_type != network_type {
+                continue;
+            }
+            // A section that doesn't parse is reported by the run of its own chain
+            if let Ok(assets) = crate::assets::parse_scraped_entries(section) {
+                chain_assets.insert(chain_name.clone(), assets);
+            }
+        }
+    }
+    Ok(chain_assets)
+}
+
+/// Log the warnings and error out when any of `diagnostics` is an error
+pub fn assert_valid(
+    chain_id: &str,
+    diagnostics: &[Diagnostic],
+) -> Result<(), AbstractInterfaceError> {
+    for diagnostic in diagnostics {
+        if diagnostic.severity == Severity::Warning {
+            log::warn!("{}: {}", chain_id, diagnostic);
+        }
+    }
+    let errors: Vec<String> = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .map(|diagnostic| format!("  {diagnostic}"))
+        .collect();
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(CwOrchError::StdErr(format!(
+        "scraped data for {} has {} validation errors:\n{}",
+        chain_id,
+        errors.len(),
+        errors.join("\n")
+    ))
+    .into())
+}
@@ -0,0 +1,116 @@
+//! Bech32 addresses of cw20 assets, contracts and pools
+
+use abstract_core::objects::pool_id::PoolAddressBase;
+use bech32::{FromBase32, Variant};
+use cw_asset::AssetInfoBase;
+
+use super::Diagnostic;
+use crate::journal::contract_key;
+use crate::{AnsData, EntityType};
+
+/// Account addresses are 20 bytes, contract addresses 32
+const ADDRESS_LENGTHS: [usize; 2] = [20, 32];
+
+/// The rule `address` breaks on a chain with bech32 prefix `prefix`, and why
+pub fn check_address(address: &str, prefix: &str) -> Option<(&'static str, String)> {
+    let (hrp, data, variant) = match bech32::decode(address) {
+        Ok(decoded) => decoded,
+        Err(e) => return Some(("address-invalid", format!("`{address}` is not bech32: {e}"))),
+    };
+    if variant != Variant::Bech32 {
+        return Some(("address-invalid", format!("`{address}` is bech32m")));
+    }
+    if hrp != prefix {
+        return Some((
+            "address-prefix",
+            format!("`{address}` has prefix `{hrp}`, expected `{prefix}`"),
+        ));
+    }
+    let length = Vec::<u8>::from_base32(&data).map(|bytes| bytes.len());
+    match length {
+        Ok(length) if ADDRESS_LENGTHS.contains(&length) => None,
+        Ok(length) => Some((
+            "address-length",
+            format!("`{address}` is {length} bytes, expected 20 or 32"),
+        )),
+        Err(e) => Some(("address-invalid", format!("`{address}`: {e}"))),
+    }
+}
+
+pub fn validate_addresses(scraped_entries: &AnsData, prefix: &str) -> Vec<Diagnostic> {
+    let mut addresses: Vec<(EntityType, String, &str)> = vec![];
+    for (name, info) in &scraped_entries.assets {
+        if let AssetInfoBase::Cw20(address) = info {
+            addresses.push((EntityType::Assets, name.clone(), address));
+        }
+    }
+    for (entry, address) in &scraped_entries.contracts {
+        addresses.push((EntityType::Contracts, contract_key(entry), address));
+    }
+    for address in scraped_entries.pools.keys() {
+        match address {
+            PoolAddressBase::Contract(contract) => {
+                addresses.push((EntityType::Pools, contract.clone(), contract));
+            }
+            PoolAddressBase::SeparateAddresses { swap, liquidity } => {
+                let key = format!("{swap},{liquidity}");
+                addresses.push((EntityType::Pools, key.clone(), swap));
+                addresses.push((EntityType::Pools, key, liquidity));
+            }
+            PoolAddressBase::Id(_) => {}
+        }
+    }
+    addresses.sort_by(|(_, a_key, a), (_, b_key, b)| (a_key, a).cmp(&(b_key, b)));
+
+    addresses
+        .into_iter()
+        .filter_map(|(entity, key, address)| {
+            let (rule, message) = check_address(address, prefix)?;
+            Some(Diagnostic::error(rule, entity, &key, message))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const JUNO_CONTRACT: &str = "juno1lqhg97uxqlm7qhl4dylm2ynzf6z8r3px9epc23epkcu3703tal7qwj6vun";
+    const TERRA_CONTRACT: &str = "terra14jrkre4r968thdmgdwrnu3q79z7vsn4jvjwyrfyjy5xh370nqegssz5ghw";
+
+    #[test]
+    fn accepts_chain_addresses() {
+        assert_eq!(check_address(JUNO_CONTRACT, "juno"), None);
+        assert_eq!(check_address(TERRA_CONTRACT, "terra"), None);
+    }
+
+    #[test]
+    fn flags_invalid_addresses() {
+        assert_eq!(
+            check_address(TERRA_CONTRACT, "juno").unwrap().0,
+            "address-prefix"
+        );
+        // last character changed, the checksum doesn't match anymore
+        let typo = JUNO_CONTRACT.replace("j6vun", "j6vum");
+        assert_eq!(check_address(&typo, "juno").unwrap().0, "address-invalid");
+    }
+
+    #[test]
+    fn flags_cw20_of_other_chain() {
+        let mut scraped = AnsData::default();
+        scraped.assets.insert(
+            "terra2>neb".to_string(),
+            AssetInfoBase::Cw20(TERRA_CONTRACT.to_string()),
+        );
+        scraped.assets.insert(
+            "juno>dao".to_string(),
+            AssetInfoBase::Cw20(JUNO_CONTRACT.to_string()),
+        );
+
+        let diagnostics = validate_addresses(&scraped, "juno");
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].key, "terra2>neb");
+        assert_eq!(diagnostics[0].rule, "address-prefix");
+    }
+}
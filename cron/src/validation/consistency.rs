@@ -0,0 +1,239 @@
+//! Consistency of the IBC assets of every scraped chain with the assets of their origin chains
+
+use std::fmt;
+
+use cw_asset::AssetInfoBase;
+use serde::Serialize;
+
+use super::ibc::{ibc_denom, ChainAssets, Channels};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// An `origin>symbol` asset of a chain that the origin chain doesn't list
+    MissingOnOrigin,
+    /// An `ibc/` asset that isn't the base denom of the origin entry over any channel to the origin chain
+    TraceMismatch,
+    /// An `ibc/` asset of a chain that has no channel to the origin chain in the channels file
+    ChannelUnknown,
+    /// An asset of the origin chain that a chain listing other assets of that origin doesn't list
+    OnlyOnOrigin,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub network_type: String,
+    /// Scraper name of the chain the asset is (or isn't) listed on
+    pub chain: String,
+    pub origin: String,
+    pub asset: String,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} {} `{}` on {}: {}",
+            self.kind, self.network_type, self.asset, self.chain, self.message
+        )
+    }
+}
+
+/// The origin chain of a single-hop `origin>symbol` asset name
+fn single_hop_origin(name: &str) -> Option<&str> {
+    let (origin, symbol) = name.split_once('>')?;
+    (!symbol.contains('>')).then_some(origin)
+}
+
+/// Compare the single-hop IBC assets of every chain in `chain_assets` with the assets of their origin chain.
+/// Origin chains that aren't in `chain_assets` can't be compared and are left out. Multi-hop `a>b>c` assets
+/// are left out as well: their denom hashes the path over every hop, which one channel per pair can't give.
+pub fn check_consistency(
+    network_type: &str,
+    chain_assets: &ChainAssets,
+    channels: &Channels,
+) -> Vec<Finding> {
+    let mut chains: Vec<&String> = chain_assets.keys().collect();
+    chains.sort();
+
+    let mut findings = vec![];
+    for chain in chains {
+        let assets = &chain_assets[chain];
+        let finding = |kind, origin: &str, asset: &str, message| Finding {
+            kind,
+            network_type: network_type.to_string(),
+            chain: chain.clone(),
+            origin: origin.to_string(),
+            asset: asset.to_string(),
+            message,
+        };
+
+        let mut names: Vec<&String> = assets.keys().collect();
+        names.sort();
+        let mut origins: Vec<&str> = vec![];
+        for name in names {
+            let Some(origin) = single_hop_origin(name) else {
+                continue;
+            };
+            let Some(origin_assets) = chain_assets.get(origin).filter(|_| origin != chain) else {
+                continue;
+            };
+            if !origins.contains(&origin) {
+                origins.push(origin);
+            }
+            let Some(origin_info) = origin_assets.get(name) else {
+                findings.push(finding(
+                    FindingKind::MissingOnOrigin,
+                    origin,
+                    name,
+                    format!("{origin} has no `{name}`"),
+                ));
+                continue;
+            };
+
+            let (AssetInfoBase::Native(denom), AssetInfoBase::Native(base_denom)) =
+                (&assets[name], origin_info)
+            else {
+                continue;
+            };
+            if !denom.starts_with("ibc/") || base_denom.starts_with("ibc/") {
+                continue;
+            }
+            let chain_channels = channels
+                .get(chain)
+                .and_then(|counterparties| counterparties.get(origin))
+                .filter(|chain_channels| !chain_channels.is_empty());
+            let Some(chain_channels) = chain_channels else {
+                findings.push(finding(
+                    FindingKind::ChannelUnknown,
+                    origin,
+                    name,
+                    format!("no channel from {chain} to {origin}, `{denom}` can't be checked"),
+                ));
+                continue;
+            };
+            if !chain_channels
+                .iter()
+                .any(|channel| ibc_denom(channel, base_denom) == *denom)
+            {
+                findings.push(finding(
+                    FindingKind::TraceMismatch,
+                    origin,
+                    name,
+                    format!(
+                        "`{denom}` is not `{base_denom}` of {origin} over {}",
+                        chain_channels.join(" or ")
+                    ),
+                ));
+            }
+        }
+
+        // Only origins the chain already lists assets of are connected to it
+        for origin in origins {
+            let mut origin_names: Vec<&String> = chain_assets[origin]
+                .keys()
+                .filter(|name| single_hop_origin(name) == Some(origin))
+                .filter(|name| !assets.contains_key(*name))
+                .collect();
+            origin_names.sort();
+            for name in origin_names {
+                findings.push(finding(
+                    FindingKind::OnlyOnOrigin,
+                    origin,
+                    name,
+                    format!("listed on {origin} but not on {chain}"),
+                ));
+            }
+        }
+    }
+    findings.sort_by_key(|finding| finding.kind);
+    findings
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn native(denom: &str) -> AssetInfoBase<String> {
+        AssetInfoBase::Native(denom.to_string())
+    }
+
+    #[test]
+    fn compares_ibc_assets_with_their_origin() {
+        let chain_assets = ChainAssets::from([
+            (
+                "juno".to_string(),
+                HashMap::from([
+                    ("juno>juno".to_string(), native("ujuno")),
+                    ("juno>wynd".to_string(), native("factory/wynd")),
+                ]),
+            ),
+            (
+                "osmosis".to_string(),
+                HashMap::from([
+                    (
+                        "juno>juno".to_string(),
+                        native(&ibc_denom("channel-42", "ujunox")),
+                    ),
+                    ("juno>neta".to_string(), native("ibc/NETA")),
+                    ("cosmoshub>atom".to_string(), native("ibc/ATOM")),
+                ]),
+            ),
+            (
+                "stargaze".to_string(),
+                HashMap::from([
+                    ("juno>juno".to_string(), native("ibc/JUNO")),
+                    ("juno>wynd".to_string(), native("ibc/WYND")),
+                    ("osmosis>juno>neta".to_string(), native("ibc/NETA")),
+                ]),
+            ),
+        ]);
+        let channels = Channels::from([(
+            "osmosis".to_string(),
+            HashMap::from([("juno".to_string(), vec!["channel-42".to_string()])]),
+        )]);
+
+        let findings: Vec<_> = check_consistency("mainnet", &chain_assets, &channels)
+            .into_iter()
+            .map(|finding| (finding.kind, finding.asset))
+            .collect();
+
+        assert_eq!(
+            findings,
+            vec![
+                (FindingKind::MissingOnOrigin, "juno>neta".to_string()),
+                (FindingKind::TraceMismatch, "juno>juno".to_string()),
+                (FindingKind::ChannelUnknown, "juno>juno".to_string()),
+                (FindingKind::ChannelUnknown, "juno>wynd".to_string()),
+                (FindingKind::OnlyOnOrigin, "juno>wynd".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_channel_lists_are_unknown_channels() {
+        let chain_assets = ChainAssets::from([
+            (
+                "juno".to_string(),
+                HashMap::from([("juno>juno".to_string(), native("ujuno"))]),
+            ),
+            (
+                "osmosis".to_string(),
+                HashMap::from([("juno>juno".to_string(), native("ibc/JUNO"))]),
+            ),
+        ]);
+        let channels = Channels::from([(
+            "osmosis".to_string(),
+            HashMap::from([("juno".to_string(), vec![])]),
+        )]);
+
+        let findings = check_consistency("mainnet", &chain_assets, &channels);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::ChannelUnknown);
+    }
+}
@@ -0,0 +1,168 @@
+//! Entries the scraper lists more than once, which silently collapse when a section is collected into a map
+
+use std::collections::{BTreeMap, HashSet};
+
+use abstract_interface::AbstractInterfaceError;
+use serde_json::{from_value, Value};
+
+use super::{Diagnostic, Severity};
+use crate::journal::{contract_key, pool_address_key};
+use crate::EntityType;
+
+/// A `[key, value]` entry of a scraped section, at `index` in the section
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawEntry {
+    pub index: usize,
+    pub key: String,
+    /// Compact JSON of the value
+    pub value: String,
+}
+
+/// The entries of a scraped section in file order, keyed like the diagnostics of `entity`
+pub fn raw_entries(
+    entity: EntityType,
+    section: &Value,
+) -> Result<Vec<RawEntry>, AbstractInterfaceError> {
+    let Some(entries) = section.as_array() else {
+        return Ok(vec![]);
+    };
+    let mut raw_entries = vec![];
+    for (index, entry) in entries.iter().enumerate() {
+        // Malformed entries are reported by the parser of the section
+        let Some([key, value]) = entry.as_array().map(Vec::as_slice) else {
+            continue;
+        };
+        let key = match entity {
+            EntityType::Contracts => contract_key(&from_value(key.clone())?),
+            EntityType::Pools => pool_address_key(&from_value(key.clone())?),
+            _ => key.as_str().map_or_else(|| key.to_string(), str::to_owned),
+        };
+        raw_entries.push(RawEntry {
+            index,
+            key,
+            value: value.to_string(),
+        });
+    }
+    Ok(raw_entries)
+}
+
+/// Report keys that are listed more than once and, with `check_values`, values that are listed under several keys
+pub fn find_duplicates(
+    entity: EntityType,
+    entries: &[RawEntry],
+    check_values: bool,
+    severity: Severity,
+) -> Vec<Diagnostic> {
+    let diagnostic = |rule, key: &str, message| Diagnostic {
+        severity,
+        ..Diagnostic::error(rule, entity, key, message)
+    };
+    let mut diagnostics = vec![];
+
+    let mut by_key: BTreeMap<&str, Vec<&RawEntry>> = BTreeMap::new();
+    for entry in entries {
+        by_key.entry(&entry.key).or_default().push(entry);
+    }
+    for (key, listed) in by_key.into_iter().filter(|(_, listed)| listed.len() > 1) {
+        let conflicting = listed.iter().any(|entry| entry.value != listed[0].value);
+        let message = if conflicting {
+            format!(
+                "listed at indices {} with different values, only the last one is registered",
+                indices(&listed)
+            )
+        } else {
+            format!("listed at indices {}", indices(&listed))
+        };
+        diagnostics.push(diagnostic("duplicate-key", key, message));
+    }
+
+    if !check_values {
+        return diagnostics;
+    }
+    let mut by_value: BTreeMap<&str, Vec<&RawEntry>> = BTreeMap::new();
+    for entry in entries {
+        by_value.entry(&entry.value).or_default().push(entry);
+    }
+    for (value, mut listed) in by_value {
+        let mut keys = HashSet::new();
+        listed.retain(|&entry| keys.insert(entry.key.as_str()));
+        if listed.len() < 2 {
+            continue;
+        }
+        let others: Vec<String> = listed[1..]
+            .iter()
+            .map(|entry| format!("`{}`", entry.key))
+            .collect();
+        diagnostics.push(diagnostic(
+            "duplicate-value",
+            &listed[0].key,
+            format!(
+                "`{value}` is also listed as {}, at indices {}",
+                others.join(", "),
+                indices(&listed)
+            ),
+        ));
+    }
+    diagnostics
+}
+
+fn indices(entries: &[&RawEntry]) -> String {
+    let indices: Vec<String> = entries
+        .iter()
+        .map(|entry| entry.index.to_string())
+        .collect();
+    indices.join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn rules(diagnostics: Vec<Diagnostic>) -> Vec<(String, String)> {
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| (diagnostic.rule, diagnostic.key))
+            .collect()
+    }
+
+    #[test]
+    fn flags_duplicate_asset_names_and_denoms() {
+        let section = json!([
+            ["juno>juno", {"native": "ujuno"}],
+            ["juno>atom", {"native": "ibc/C4CF"}],
+            ["juno>juno", {"native": "ujunox"}],
+            ["cosmoshub>atom", {"native": "ibc/C4CF"}],
+        ]);
+        let entries = raw_entries(EntityType::Assets, &section).unwrap();
+
+        let diagnostics = find_duplicates(EntityType::Assets, &entries, true, Severity::Error);
+
+        assert_eq!(
+            rules(diagnostics.clone()),
+            vec![
+                ("duplicate-key".to_string(), "juno>juno".to_string()),
+                ("duplicate-value".to_string(), "juno>atom".to_string()),
+            ]
+        );
+        assert!(diagnostics[0]
+            .message
+            .contains("indices 0, 2 with different values"));
+        assert!(diagnostics[1].message.contains("`cosmoshub>atom`"));
+    }
+
+    #[test]
+    fn allows_shared_contract_addresses() {
+        let section = json!([
+            [{"protocol": "astroport", "contract": "staking/astroport/a,b"}, "terra1gen"],
+            [{"protocol": "astroport", "contract": "staking/astroport/a,c"}, "terra1gen"],
+        ]);
+        let entries = raw_entries(EntityType::Contracts, &section).unwrap();
+
+        assert_eq!(entries[1].key, "astroport:staking/astroport/a,c");
+        assert!(
+            find_duplicates(EntityType::Contracts, &entries, false, Severity::Error).is_empty()
+        );
+    }
+}
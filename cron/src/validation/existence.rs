@@ -0,0 +1,254 @@
+//! On-chain existence of the tokens and contracts the update registers
+
+use abstract_core::objects::pool_id::PoolAddressBase;
+use abstract_interface::{AbstractInterfaceError, AnsHost};
+use cosmos_sdk_proto::cosmos::bank::v1beta1::{
+    query_client::QueryClient as BankQueryClient, QuerySupplyOfRequest,
+};
+use cosmos_sdk_proto::cosmwasm::wasm::v1::{
+    query_client::QueryClient as WasmQueryClient, QueryContractInfoRequest,
+    QuerySmartContractStateRequest,
+};
+use cw20::{Cw20QueryMsg, TokenInfoResponse};
+use cw_asset::AssetInfoBase;
+use cw_orch::prelude::*;
+use tonic::{Code, Status};
+
+use super::Diagnostic;
+use crate::journal::{contract_key, pool_address_key};
+use crate::{AnsDataDiff, EntityType};
+
+/// What a query found wrong with an entry, `None` when it exists. A query that didn't reach the node
+/// or didn't get an answer is a `Status`, any answer the node gives is turned into a problem with the entry.
+type Existence = Result<Option<String>, Status>;
+
+/// Statuses of a query that never got an answer about the entry, like an unreachable or syncing node
+const TRANSPORT_ERRORS: &[Code] = &[
+    Code::Unavailable,
+    Code::DeadlineExceeded,
+    Code::Cancelled,
+    Code::ResourceExhausted,
+    Code::Aborted,
+];
+
+fn is_transport_error(status: &Status) -> bool {
+    TRANSPORT_ERRORS.contains(&status.code())
+}
+
+/// Query the chain for every entry `diff` adds, the entries that are already registered were checked before.
+/// Queries that fail to get an answer are chain errors and stop the check.
+pub fn validate_existence(
+    ans_host: &AnsHost<Daemon>,
+    diff: &AnsDataDiff,
+) -> Result<Vec<Diagnostic>, AbstractInterfaceError> {
+    let chain = ans_host.get_chain();
+    let mut diagnostics = vec![];
+
+    let mut assets: Vec<_> = diff.assets.1.iter().collect();
+    assets.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (name, info) in assets {
+        let (rule, value, existence) = match info {
+            AssetInfoBase::Native(denom) => ("denom-supply", denom, check_supply(chain, denom)),
+            AssetInfoBase::Cw20(address) => {
+                ("cw20-token-info", address, check_cw20(chain, address))
+            }
+            _ => continue,
+        };
+        if let Some(message) = existence.map_err(|status| query_error(value, status))? {
+            diagnostics.push(Diagnostic::error(
+                rule,
+                EntityType::Assets,
+                name,
+                format!("`{value}` {message}"),
+            ));
+        }
+    }
+
+    let mut contracts: Vec<_> = diff
+        .contracts
+        .1
+        .iter()
+        .map(|(entry, address)| (contract_key(entry), address))
+        .collect();
+    contracts.sort();
+    for (key, address) in contracts {
+        let existence =
+            check_contract(chain, address).map_err(|status| query_error(address, status))?;
+        if let Some(message) = existence {
+            diagnostics.push(Diagnostic::error(
+                "contract-missing",
+                EntityType::Contracts,
+                &key,
+                format!("`{address}` {message}"),
+            ));
+        }
+    }
+
+    let mut pools: Vec<_> = diff.pools.1.keys().collect();
+    pools.sort_by_key(|address| pool_address_key(address));
+    for pool in pools {
+        let addresses = match pool {
+            PoolAddressBase::Contract(contract) => vec![contract],
+            PoolAddressBase::SeparateAddresses { swap, liquidity } => vec![swap, liquidity],
+            PoolAddressBase::Id(_) => vec![],
+        };
+        for address in addresses {
+            let existence =
+                check_contract(chain, address).map_err(|status| query_error(address, status))?;
+            if let Some(message) = existence {
+                diagnostics.push(Diagnostic::error(
+                    "contract-missing",
+                    EntityType::Pools,
+                    &pool_address_key(pool),
+                    format!("`{address}` {message}"),
+                ));
+            }
+        }
+    }
+    Ok(diagnostics)
+}
+
+fn query_error(value: &str, status: Status) -> AbstractInterfaceError {
+    CwOrchError::StdErr(format!(
+        "existence query for `{value}` failed with {:?}: {}",
+        status.code(),
+        status.message()
+    ))
+    .into()
+}
+
+fn check_contract(chain: &Daemon, address: &str) -> Existence {
+    let resp = chain
+        .rt_handle
+        .block_on(
+            WasmQueryClient::new(chain.channel()).contract_info(QueryContractInfoRequest {
+                address: address.to_string(),
+            }),
+        );
+    contract_existence(resp.map(|_| ()))
+}
+
+fn contract_existence(resp: Result<(), Status>) -> Existence {
+    match resp {
+        Ok(()) => Ok(None),
+        Err(status) if is_transport_error(&status) => Err(status),
+        Err(status) => Ok(Some(format!("is not a contract: {}", status.message()))),
+    }
+}
+
+fn check_cw20(chain: &Daemon, address: &str) -> Existence {
+    let query_data = serde_json::to_vec(&Cw20QueryMsg::TokenInfo {})
+        .map_err(|e| Status::internal(e.to_string()))?;
+    let resp =
+        chain
+            .rt_handle
+            .block_on(WasmQueryClient::new(chain.channel()).smart_contract_state(
+                QuerySmartContractStateRequest {
+                    address: address.to_string(),
+                    query_data,
+                },
+            ));
+    cw20_existence(resp.map(|resp| resp.into_inner().data))
+}
+
+fn cw20_existence(resp: Result<Vec<u8>, Status>) -> Existence {
+    match resp {
+        Ok(data) => Ok(serde_json::from_slice::<TokenInfoResponse>(&data)
+            .err()
+            .map(|e| format!("answered TokenInfo with an invalid response: {e}"))),
+        Err(status) if is_transport_error(&status) => Err(status),
+        Err(status) if status.code() == Code::NotFound => {
+            Ok(Some(format!("is not a contract: {}", status.message())))
+        }
+        // A contract that isn't a cw20 fails the query
+        Err(status) => Ok(Some(format!("is not a cw20: {}", status.message()))),
+    }
+}
+
+fn check_supply(chain: &Daemon, denom: &str) -> Existence {
+    let resp = chain
+        .rt_handle
+        .block_on(
+            BankQueryClient::new(chain.channel()).supply_of(QuerySupplyOfRequest {
+                denom: denom.to_string(),
+            }),
+        );
+    supply_existence(resp.map(|resp| resp.into_inner().amount.map(|coin| coin.amount)))
+}
+
+/// The bank module answers every denom, a denom that doesn't exist has no supply
+fn supply_existence(resp: Result<Option<String>, Status>) -> Existence {
+    let amount = resp?.unwrap_or_default();
+    if amount.is_empty() || amount == "0" {
+        return Ok(Some("has no supply".to_string()));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn only_missing_entries_are_problems() {
+        assert_eq!(contract_existence(Ok(())).unwrap(), None);
+        assert!(
+            contract_existence(Err(Status::not_found("no such contract")))
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!(
+            supply_existence(Ok(Some("1000".to_string()))).unwrap(),
+            None
+        );
+        assert!(supply_existence(Ok(Some("0".to_string())))
+            .unwrap()
+            .is_some());
+        assert!(supply_existence(Ok(None)).unwrap().is_some());
+    }
+
+    #[test]
+    fn returns_failing_queries() {
+        let unavailable = || Status::unavailable("node is syncing");
+
+        assert_eq!(
+            contract_existence(Err(unavailable())).unwrap_err().code(),
+            Code::Unavailable
+        );
+        assert_eq!(
+            supply_existence(Err(unavailable())).unwrap_err().code(),
+            Code::Unavailable
+        );
+        assert_eq!(
+            cw20_existence(Err(Status::deadline_exceeded("timeout")))
+                .unwrap_err()
+                .code(),
+            Code::DeadlineExceeded
+        );
+    }
+
+    #[test]
+    fn checks_token_info_responses() {
+        let token_info = br#"{"name":"Wynd","symbol":"WYND","decimals":6,"total_supply":"1"}"#;
+
+        assert_eq!(cw20_existence(Ok(token_info.to_vec())).unwrap(), None);
+        assert!(cw20_existence(Ok(b"{}".to_vec())).unwrap().is_some());
+        assert!(cw20_existence(Err(Status::not_found("no such contract")))
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn contracts_that_are_no_cw20_are_problems() {
+        let not_cw20 = Status::unknown("query wasm contract failed: unknown variant `token_info`");
+
+        let problem = cw20_existence(Err(not_cw20)).unwrap().unwrap();
+
+        assert!(problem.starts_with("is not a cw20"));
+        assert!(
+            contract_existence(Err(Status::invalid_argument("invalid address")))
+                .unwrap()
+                .is_some()
+        );
+    }
+}
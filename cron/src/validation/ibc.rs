@@ -0,0 +1,209 @@
+//! Offline verification of `ibc/<hash>` denoms against the channel they claim to come through
+
+use std::collections::HashMap;
+
+use abstract_interface::AbstractInterfaceError;
+use cw_asset::AssetInfoBase;
+use cw_orch::prelude::*;
+
+use super::Diagnostic;
+use crate::EntityType;
+
+/// Channel ids of every chain to its counterparties, by scraper chain name
+pub type Channels = HashMap<String, HashMap<String, Vec<String>>>;
+
+/// Scraped assets of every chain, by scraper chain name
+pub type ChainAssets = HashMap<String, HashMap<String, AssetInfoBase<String>>>;
+
+/// The denom `base_denom` gets on the chain that received it over `channel`
+pub fn ibc_denom(channel: &str, base_denom: &str) -> String {
+    let hash = sha256::digest(format!("transfer/{channel}/{base_denom}"));
+    format!("ibc/{}", hash.to_uppercase())
+}
+
+/// Parse the channels file.
+/// It's maintained by hand and can have trailing commas, which are dropped before parsing.
+pub fn parse_channels(text: &str) -> Result<Channels, AbstractInterfaceError> {
+    Ok(serde_json::from_str(&strip_trailing_commas(text))?)
+}
+
+fn strip_trailing_commas(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let rest = chars.clone().find(|c| !c.is_whitespace());
+            if matches!(rest, Some('}') | Some(']')) {
+                continue;
+            }
+        }
+        stripped.push(c);
+    }
+    stripped
+}
+
+/// Check every single-hop `origin>symbol` IBC asset of `chain_name` against the hash of
+/// `transfer/<channel>/<base denom>`, where the base denom is the native denom of the same asset on the origin chain.
+/// Multi-hop assets and assets whose origin or channel isn't in the scraped data can't be verified offline.
+pub fn validate_ibc_denoms(
+    chain_name: &str,
+    assets: &HashMap<String, AssetInfoBase<String>>,
+    chain_assets: &ChainAssets,
+    channels: &Channels,
+) -> Vec<Diagnostic> {
+    let mut names: Vec<&String> = assets.keys().collect();
+    names.sort();
+
+    let mut diagnostics = vec![];
+    for name in names {
+        let AssetInfoBase::Native(denom) = &assets[name] else {
+            continue;
+        };
+        if !denom.starts_with("ibc/") {
+            continue;
+        }
+        let error = |rule, message| Diagnostic::error(rule, EntityType::Assets, name, message);
+
+        let Some((origin, _symbol)) = name.split_once('>') else {
+            continue;
+        };
+        if origin == chain_name {
+            diagnostics.push(error(
+                "ibc-denom-origin",
+                format!("asset of {chain_name} is registered as IBC denom `{denom}`"),
+            ));
+            continue;
+        }
+        // Multi-hop hashes depend on every hop of the path
+        if name.matches('>').count() > 1 {
+            continue;
+        }
+        let Some(AssetInfoBase::Native(base_denom)) = chain_assets
+            .get(origin)
+            .and_then(|origin_assets| origin_assets.get(name))
+        else {
+            continue;
+        };
+        if base_denom.starts_with("ibc/") {
+            continue;
+        }
+        let chain_channels = channels
+            .get(chain_name)
+            .and_then(|counterparties| counterparties.get(origin))
+            .filter(|chain_channels| !chain_channels.is_empty());
+        let Some(chain_channels) = chain_channels else {
+            diagnostics.push(Diagnostic::warning(
+                "ibc-channel-unknown",
+                EntityType::Assets,
+                name,
+                format!("no channel from {chain_name} to {origin}, `{denom}` can't be verified"),
+            ));
+            continue;
+        };
+
+        let expected: Vec<String> = chain_channels
+            .iter()
+            .map(|channel| ibc_denom(channel, base_denom))
+            .collect();
+        // Either the asset or the channels file is wrong, neither may be pushed on-chain
+        if !expected.contains(denom) {
+            diagnostics.push(error(
+                "ibc-denom-mismatch",
+                format!(
+                    "`{denom}` is not `{base_denom}` of {origin} over {}, expected {}",
+                    chain_channels.join(" or "),
+                    expected.join(" or ")
+                ),
+            ));
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::validation::{assert_valid, Severity};
+
+    fn native(denom: &str) -> AssetInfoBase<String> {
+        AssetInfoBase::Native(denom.to_string())
+    }
+
+    #[test]
+    fn computes_ibc_denoms() {
+        // ATOM on osmosis
+        assert_eq!(
+            ibc_denom("channel-0", "uatom"),
+            "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2"
+        );
+    }
+
+    #[test]
+    fn parses_trailing_commas() {
+        let channels =
+            parse_channels(r#"{"archway": {"osmosis": ["channel-1",],}, "osmosis": {}}"#).unwrap();
+
+        assert_eq!(channels["archway"]["osmosis"], vec!["channel-1"]);
+    }
+
+    #[test]
+    fn flags_mismatching_denoms() {
+        let chain_assets = ChainAssets::from([(
+            "cosmoshub".to_string(),
+            HashMap::from([("cosmoshub>atom".to_string(), native("uatom"))]),
+        )]);
+        let channels = Channels::from([(
+            "osmosis".to_string(),
+            HashMap::from([("cosmoshub".to_string(), vec!["channel-0".to_string()])]),
+        )]);
+        let valid = HashMap::from([(
+            "cosmoshub>atom".to_string(),
+            native(&ibc_denom("channel-0", "uatom")),
+        )]);
+        let invalid = HashMap::from([(
+            "cosmoshub>atom".to_string(),
+            native(&ibc_denom("channel-0", "ustc")),
+        )]);
+
+        assert!(validate_ibc_denoms("osmosis", &valid, &chain_assets, &channels).is_empty());
+        let diagnostics = validate_ibc_denoms("osmosis", &invalid, &chain_assets, &channels);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].rule, "ibc-denom-mismatch");
+    }
+
+    #[test]
+    fn rejects_a_wrong_channel() {
+        let chain_assets = ChainAssets::from([(
+            "archway".to_string(),
+            HashMap::from([("archway>arch".to_string(), native("aarch"))]),
+        )]);
+        // The denom is the live channel-1429, the channels file lists another channel
+        let channels = Channels::from([(
+            "osmosis".to_string(),
+            HashMap::from([("archway".to_string(), vec!["channel-7707".to_string()])]),
+        )]);
+        let assets = HashMap::from([(
+            "archway>arch".to_string(),
+            native("ibc/23AB778D694C1ECFC59B91D8C399C115CC53B0BD1C61020D8E19519F002BDD85"),
+        )]);
+
+        let diagnostics = validate_ibc_denoms("osmosis", &assets, &chain_assets, &channels);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].rule, "ibc-denom-mismatch");
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert!(diagnostics[0].message.contains("channel-7707"));
+        assert!(assert_valid("osmosis-1", &diagnostics).is_err());
+    }
+}
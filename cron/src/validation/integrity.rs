@@ -0,0 +1,167 @@
+//! References between entries: pools refer to assets and dexes, staking contracts to pools
+
+use super::Diagnostic;
+use crate::journal::{contract_key, pool_address_key};
+use crate::{AnsData, EntityType};
+
+/// The dex and asset names of a `staking/{dex}/{a},{b}` contract entry.
+/// Older entries leave out the dex, it's the protocol of the entry then.
+pub fn staking_pairing<'a>(
+    protocol: &'a str,
+    contract: &'a str,
+) -> Option<(&'a str, Vec<&'a str>)> {
+    let pairing = contract.strip_prefix("staking/")?;
+    let (dex, assets) = match pairing.split_once('/') {
+        Some((dex, assets)) if !dex.contains('>') => (dex, assets),
+        _ => (protocol, pairing),
+    };
+    Some((dex, assets.split(',').collect()))
+}
+
+/// Check the references of the scraped entries against the entries the ANS host holds after the update
+pub fn validate_references(scraped_entries: &AnsData, registered: &AnsData) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+
+    let mut pools: Vec<_> = scraped_entries
+        .pools
+        .iter()
+        .map(|(address, (_, metadata))| (pool_address_key(address), metadata))
+        .collect();
+    pools.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (key, metadata) in pools {
+        for asset in &metadata.assets {
+            if !registered.assets.contains_key(asset.as_str()) {
+                diagnostics.push(Diagnostic::error(
+                    "pool-asset-unknown",
+                    EntityType::Pools,
+                    &key,
+                    format!("pool asset `{asset}` is not a registered asset"),
+                ));
+            }
+        }
+        if !registered.dexes.contains_key(&metadata.dex) {
+            diagnostics.push(Diagnostic::error(
+                "pool-dex-unknown",
+                EntityType::Pools,
+                &key,
+                format!("dex `{}` is not registered", metadata.dex),
+            ));
+        }
+    }
+
+    let mut contracts: Vec<_> = scraped_entries.contracts.keys().collect();
+    contracts.sort_by_key(|entry| contract_key(entry));
+    for entry in contracts {
+        let Some((dex, assets)) = staking_pairing(&entry.protocol, &entry.contract) else {
+            continue;
+        };
+        let paired = registered.pools.values().any(|(_, metadata)| {
+            metadata.dex == dex
+                && assets
+                    .iter()
+                    .all(|asset| metadata.assets.iter().any(|a| a.as_str() == *asset))
+        });
+        if !paired {
+            diagnostics.push(Diagnostic::error(
+                "staking-pool-unknown",
+                EntityType::Contracts,
+                &contract_key(entry),
+                format!("no {dex} pool of {}", assets.join(" and ")),
+            ));
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod test {
+    use abstract_core::objects::pool_id::PoolAddressBase;
+    use abstract_core::objects::{
+        AssetEntry, PoolMetadata, PoolType, UncheckedContractEntry, UniquePoolId,
+    };
+    use cw_asset::AssetInfoBase;
+
+    use super::*;
+
+    fn registered() -> AnsData {
+        let mut data = AnsData::default();
+        for asset in ["juno>juno", "juno>wynd"] {
+            data.assets
+                .insert(asset.to_string(), AssetInfoBase::Native(asset.to_string()));
+        }
+        data.dexes
+            .insert("wyndex".to_string(), "wyndex".to_string());
+        data.pools.insert(
+            PoolAddressBase::Contract("juno1pool".to_string()),
+            (
+                UniquePoolId::new(1),
+                PoolMetadata {
+                    dex: "wyndex".to_string(),
+                    pool_type: PoolType::ConstantProduct,
+                    assets: vec![AssetEntry::new("juno>juno"), AssetEntry::new("juno>wynd")],
+                },
+            ),
+        );
+        data
+    }
+
+    #[test]
+    fn parses_staking_pairings() {
+        assert_eq!(
+            staking_pairing("wyndex", "staking/wyndex/juno>juno,juno>wynd"),
+            Some(("wyndex", vec!["juno>juno", "juno>wynd"]))
+        );
+        assert_eq!(
+            staking_pairing("astroport", "staking/sei2>factory/sei1abc/usdc,sei2>sei"),
+            Some(("astroport", vec!["sei2>factory/sei1abc/usdc", "sei2>sei"]))
+        );
+        assert_eq!(staking_pairing("croncat", "factory"), None);
+    }
+
+    #[test]
+    fn flags_unresolvable_references() {
+        let mut scraped = registered();
+        scraped.pools.insert(
+            PoolAddressBase::Contract("juno1other".to_string()),
+            (
+                UniquePoolId::new(0),
+                PoolMetadata {
+                    dex: "junoswap".to_string(),
+                    pool_type: PoolType::ConstantProduct,
+                    assets: vec![AssetEntry::new("juno>juno"), AssetEntry::new("juno>atom")],
+                },
+            ),
+        );
+        scraped.contracts.insert(
+            UncheckedContractEntry {
+                protocol: "wyndex".to_string(),
+                contract: "staking/wyndex/juno>juno,juno>wynd".to_string(),
+            },
+            "juno1staking".to_string(),
+        );
+        scraped.contracts.insert(
+            UncheckedContractEntry {
+                protocol: "wyndex".to_string(),
+                contract: "staking/wyndex/juno>atom,juno>juno".to_string(),
+            },
+            "juno1staking".to_string(),
+        );
+
+        let rules: Vec<_> = validate_references(&scraped, &registered())
+            .into_iter()
+            .map(|diagnostic| (diagnostic.key, diagnostic.rule))
+            .collect();
+
+        assert_eq!(
+            rules,
+            vec![
+                ("juno1other".to_string(), "pool-asset-unknown".to_string()),
+                ("juno1other".to_string(), "pool-dex-unknown".to_string()),
+                (
+                    "wyndex:staking/wyndex/juno>atom,juno>juno".to_string(),
+                    "staking-pool-unknown".to_string()
+                ),
+            ]
+        );
+    }
+}
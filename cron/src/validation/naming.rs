@@ -0,0 +1,143 @@
+//! Naming conventions of the asset keys: `chain>symbol` for native and IBC assets,
+//! `a>b>symbol` for multi-hop assets and `dex/a,b` for LP tokens
+
+use std::collections::{HashMap, HashSet};
+
+use cw_asset::AssetInfoBase;
+
+use super::Diagnostic;
+use crate::EntityType;
+
+/// Split an LP token name into its dex and the names of its assets
+pub fn lp_parts(name: &str) -> Option<(&str, Vec<&str>)> {
+    let (dex, assets) = name.split_once('/')?;
+    // `sei2>factory/...` is a native asset whose symbol contains a slash
+    if dex.contains('>') {
+        return None;
+    }
+    Some((dex, assets.split(',').collect()))
+}
+
+fn is_allowed_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || ">/,._-".contains(c)
+}
+
+pub fn validate_asset_names(
+    assets: &HashMap<String, AssetInfoBase<String>>,
+    known_chains: &HashSet<String>,
+) -> Vec<Diagnostic> {
+    let mut names: Vec<&String> = assets.keys().collect();
+    names.sort();
+
+    let mut diagnostics = vec![];
+    for name in names {
+        diagnostics.extend(validate_asset_name(name, assets, known_chains));
+    }
+    diagnostics
+}
+
+fn validate_asset_name(
+    name: &str,
+    assets: &HashMap<String, AssetInfoBase<String>>,
+    known_chains: &HashSet<String>,
+) -> Vec<Diagnostic> {
+    let error = |rule, message| Diagnostic::error(rule, EntityType::Assets, name, message);
+    let mut diagnostics = vec![];
+
+    if name != name.to_lowercase() {
+        diagnostics.push(error("asset-name-case", "must be lowercase".to_string()));
+    }
+    if let Some(c) = name.to_lowercase().chars().find(|c| !is_allowed_char(*c)) {
+        diagnostics.push(error(
+            "asset-name-chars",
+            format!("contains `{c}`, only a-z, 0-9 and `>/,._-` are allowed"),
+        ));
+    }
+
+    if let Some((dex, parts)) = lp_parts(name) {
+        if dex.is_empty() || parts.len() < 2 || parts.iter().any(|part| part.is_empty()) {
+            diagnostics.push(error(
+                "asset-name-format",
+                "LP tokens are named `dex/asset,asset`".to_string(),
+            ));
+        }
+        // The assets of the pair are validated as entries of their own
+        for part in parts.into_iter().filter(|part| !part.is_empty()) {
+            if !assets.contains_key(part) {
+                diagnostics.push(error(
+                    "lp-asset-unknown",
+                    format!("pool asset `{part}` is not a registered asset"),
+                ));
+            }
+        }
+        return diagnostics;
+    }
+
+    let segments: Vec<&str> = name.split('>').collect();
+    if segments.len() < 2 || segments.iter().any(|segment| segment.is_empty()) {
+        diagnostics.push(error(
+            "asset-name-format",
+            "assets are named `chain>symbol` or `chain>chain>symbol`".to_string(),
+        ));
+        return diagnostics;
+    }
+    for chain in &segments[..segments.len() - 1] {
+        if !known_chains.contains(&chain.to_lowercase()) {
+            diagnostics.push(error(
+                "asset-name-chain",
+                format!("`{chain}` is not a known chain"),
+            ));
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rules(names: &[&str]) -> Vec<(String, String)> {
+        let assets = names
+            .iter()
+            .map(|name| (name.to_string(), AssetInfoBase::Native("u".to_string())))
+            .collect();
+        let known_chains = HashSet::from(["juno".to_string(), "eth".to_string()]);
+        validate_asset_names(&assets, &known_chains)
+            .into_iter()
+            .map(|diagnostic| (diagnostic.key, diagnostic.rule))
+            .collect()
+    }
+
+    #[test]
+    fn accepts_conventional_names() {
+        assert!(rules(&[
+            "juno>juno",
+            "eth>juno>usdc",
+            "juno>factory/juno1abc/ulp",
+            "wyndex/juno>juno,eth>juno>usdc",
+        ])
+        .is_empty());
+    }
+
+    #[test]
+    fn flags_violations() {
+        assert_eq!(
+            rules(&[
+                "Juno>juno",
+                "juno",
+                "juno>juno",
+                "osmo>osmo",
+                "wyndex/juno>juno,juno>wynd"
+            ]),
+            vec![
+                ("Juno>juno".to_string(), "asset-name-case".to_string()),
+                ("juno".to_string(), "asset-name-format".to_string()),
+                ("osmo>osmo".to_string(), "asset-name-chain".to_string()),
+                (
+                    "wyndex/juno>juno,juno>wynd".to_string(),
+                    "lp-asset-unknown".to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! Address variants, pool types and asset counts of pools, per dex
+
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use abstract_core::objects::pool_id::{PoolAddressBase, UncheckedPoolAddress};
+use abstract_core::objects::{PoolMetadata, PoolType, UniquePoolId};
+
+use super::Diagnostic;
+use crate::config::{DexRules, PoolAddressKind};
+use crate::journal::pool_address_key;
+use crate::EntityType;
+
+pub fn address_kind(address: &UncheckedPoolAddress) -> PoolAddressKind {
+    match address {
+        PoolAddressBase::Id(_) => PoolAddressKind::Id,
+        PoolAddressBase::Contract(_) => PoolAddressKind::Contract,
+        PoolAddressBase::SeparateAddresses { .. } => PoolAddressKind::SeparateAddresses,
+    }
+}
+
+/// Number of assets a pool of `pool_type` can hold
+pub fn asset_counts(pool_type: &PoolType) -> RangeInclusive<usize> {
+    match pool_type {
+        PoolType::ConstantProduct | PoolType::ConcentratedLiquidity => 2..=2,
+        _ => 2..=8,
+    }
+}
+
+/// Check every pool against the rules of its dex in `dexes`
+pub fn validate_pools(
+    pools: &HashMap<UncheckedPoolAddress, (UniquePoolId, PoolMetadata)>,
+    dexes: &HashMap<String, DexRules>,
+) -> Vec<Diagnostic> {
+    let mut pools: Vec<_> = pools
+        .iter()
+        .map(|(address, (_, metadata))| (pool_address_key(address), address, metadata))
+        .collect();
+    pools.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+
+    let mut diagnostics = vec![];
+    for (key, address, metadata) in pools {
+        let error = |rule, message| Diagnostic::error(rule, EntityType::Pools, &key, message);
+        let dex = &metadata.dex;
+        let pool_type = &metadata.pool_type;
+
+        let counts = asset_counts(pool_type);
+        if !counts.contains(&metadata.assets.len()) {
+            diagnostics.push(error(
+                "pool-asset-count",
+                format!(
+                    "{pool_type:?} pool has {} assets, expected {} to {}",
+                    metadata.assets.len(),
+                    counts.start(),
+                    counts.end()
+                ),
+            ));
+        }
+
+        let Some(rules) = dexes.get(dex) else {
+            diagnostics.push(Diagnostic::warning(
+                "pool-dex-unlisted",
+                EntityType::Pools,
+                &key,
+                format!("dex `{dex}` has no pool rules in the config"),
+            ));
+            continue;
+        };
+        let kind = address_kind(address);
+        if !rules.addresses.contains(&kind) {
+            diagnostics.push(error(
+                "pool-address-kind",
+                format!(
+                    "{dex} pools are addressed by {:?}, not {kind:?}",
+                    rules.addresses
+                ),
+            ));
+        }
+        if !rules.pool_types.contains(pool_type) {
+            diagnostics.push(error(
+                "pool-type-unsupported",
+                format!("{dex} has no {pool_type:?} pools"),
+            ));
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod test {
+    use abstract_core::objects::AssetEntry;
+
+    use super::*;
+
+    fn pool(dex: &str, pool_type: PoolType, assets: &[&str]) -> (UniquePoolId, PoolMetadata) {
+        (
+            UniquePoolId::new(0),
+            PoolMetadata {
+                dex: dex.to_string(),
+                pool_type,
+                assets: assets.iter().map(|asset| AssetEntry::new(asset)).collect(),
+            },
+        )
+    }
+
+    #[test]
+    fn bundled_rules_accept_scraped_pools() {
+        let dexes = crate::config::Config::default().dexes;
+        let pools = HashMap::from([
+            (
+                PoolAddressBase::Id(1),
+                pool("osmosis", PoolType::ConcentratedLiquidity, &["a", "b"]),
+            ),
+            (
+                PoolAddressBase::SeparateAddresses {
+                    swap: "kujira1swap".to_string(),
+                    liquidity: "kujira1lp".to_string(),
+                },
+                pool("kujira", PoolType::ConstantProduct, &["a", "b"]),
+            ),
+        ]);
+
+        assert!(validate_pools(&pools, &dexes).is_empty());
+    }
+
+    #[test]
+    fn flags_pools_breaking_dex_rules() {
+        let dexes = crate::config::Config::default().dexes;
+        let pools = HashMap::from([
+            (
+                PoolAddressBase::Id(1),
+                pool("astroport", PoolType::ConstantProduct, &["a", "b", "c"]),
+            ),
+            (
+                PoolAddressBase::Contract("wyndex".to_string()),
+                pool("wyndex", PoolType::ConcentratedLiquidity, &["a", "b"]),
+            ),
+        ]);
+
+        let rules: Vec<_> = validate_pools(&pools, &dexes)
+            .into_iter()
+            .map(|diagnostic| diagnostic.rule)
+            .collect();
+
+        assert_eq!(
+            rules,
+            vec![
+                "pool-asset-count",
+                "pool-address-kind",
+                "pool-type-unsupported"
+            ]
+        );
+    }
+}
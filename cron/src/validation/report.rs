@@ -0,0 +1,368 @@
+//! Validation results of several chains in one JSON report with SARIF-style locations, for CI annotations
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use abstract_interface::AbstractInterfaceError;
+use cw_orch::prelude::*;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::{duplicates, scraped_file, Diagnostic, Severity};
+use crate::{EntityType, ScrapedFiles};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Rule of the results of chains that couldn't be validated at all
+pub const CHAIN_ERROR_RULE: &str = "chain-error";
+
+/// Position of the first entry of every key in the scraped sections of a chain
+pub type EntryPositions = HashMap<EntityType, HashMap<String, EntryPosition>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct EntryPosition {
+    /// Index of the entry in the chain section
+    pub index: usize,
+    /// Line the entry starts at in the scraped file, 1-based
+    pub line: Option<usize>,
+}
+
+/// Where a diagnostic points at in the scraped data
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Location {
+    /// Scraped file relative to the root of the scraper repository, e.g. `out/assets.json`.
+    /// Unknown for chains that couldn't be validated.
+    pub file: Option<String>,
+    /// Scraper chain name, the top level key of the file
+    pub chain: String,
+    pub chain_id: String,
+    /// Unknown for entries that are derived from other entries
+    pub position: Option<EntryPosition>,
+}
+
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    results: Vec<(Location, Diagnostic)>,
+    /// Chains that couldn't be validated, with the reason
+    chain_errors: Vec<(Location, String)>,
+}
+
+/// Path of the scraped file of `entity` in the scraper repository
+fn repository_file(entity: EntityType) -> String {
+    format!("out/{}.json", scraped_file(entity))
+}
+
+/// Locate the entries of the scraped sections of a chain by key, in the files that were fetched
+pub fn entry_positions(
+    files: &ScrapedFiles,
+    chain_name: &str,
+    chain_id: &str,
+) -> Result<EntryPositions, AbstractInterfaceError> {
+    let mut positions = EntryPositions::new();
+    for entity in [EntityType::Contracts, EntityType::Assets, EntityType::Pools] {
+        let file = scraped_file(entity);
+        if !files.contains(file) {
+            continue;
+        }
+        let raw_scraped_entries = files.json(file)?;
+        let entries = duplicates::raw_entries(entity, &raw_scraped_entries[chain_name][chain_id])?;
+        let lines = entry_lines(files.text(file)?, chain_name, chain_id);
+        let entity_positions = positions.entry(entity).or_default();
+        for entry in entries {
+            entity_positions.entry(entry.key).or_insert(EntryPosition {
+                index: entry.index,
+                line: lines.get(entry.index).copied(),
+            });
+        }
+    }
+    Ok(positions)
+}
+
+/// Container of the JSON value being scanned, with the last key of an object
+enum Frame {
+    Object(Option<String>),
+    Array,
+}
+
+/// Lines the entries of the `chain_name` / `chain_id` section of a scraped JSON file start at, in section order
+pub fn entry_lines(text: &str, chain_name: &str, chain_id: &str) -> Vec<usize> {
+    let mut lines = vec![];
+    let mut line = 1;
+    let mut stack: Vec<Frame> = vec![];
+    let mut last_string = String::new();
+    let mut chars = text.chars().peekable();
+    let is_literal_char = |c: &char| c.is_alphanumeric() || "+-.".contains(*c);
+    while let Some(c) = chars.next() {
+        let in_section = matches!(
+            stack.as_slice(),
+            [Frame::Object(Some(name)), Frame::Object(Some(id)), Frame::Array]
+                if name == chain_name && id == chain_id
+        );
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() || c == ',' => {}
+            ':' => {
+                if let Some(Frame::Object(key)) = stack.last_mut() {
+                    *key = Some(std::mem::take(&mut last_string));
+                }
+            }
+            '}' | ']' => {
+                stack.pop();
+            }
+            c => {
+                if in_section {
+                    lines.push(line);
+                }
+                match c {
+                    '{' => stack.push(Frame::Object(None)),
+                    '[' => stack.push(Frame::Array),
+                    '"' => {
+                        last_string.clear();
+                        while let Some(c) = chars.next() {
+                            match c {
+                                '"' => break,
+                                '\\' => last_string.extend(chars.next()),
+                                c => last_string.push(c),
+                            }
+                        }
+                    }
+                    // Numbers and literals
+                    _ => while chars.next_if(is_literal_char).is_some() {},
+                }
+            }
+        }
+    }
+    lines
+}
+
+impl ValidationReport {
+    /// Add the diagnostics of a chain, located with the `positions` of its scraped entries
+    pub fn add_chain(
+        &mut self,
+        chain_name: &str,
+        chain_id: &str,
+        diagnostics: Vec<Diagnostic>,
+        positions: &EntryPositions,
+    ) {
+        for diagnostic in diagnostics {
+            let position = positions
+                .get(&diagnostic.entity)
+                .and_then(|entity_positions| entity_positions.get(&diagnostic.key))
+                .copied();
+            let location = Location {
+                file: Some(repository_file(diagnostic.entity)),
+                chain: chain_name.to_string(),
+                chain_id: chain_id.to_string(),
+                position,
+            };
+            self.results.push((location, diagnostic));
+        }
+    }
+
+    /// Record a chain whose data couldn't be validated
+    pub fn add_chain_error(&mut self, chain_name: &str, chain_id: &str, message: String) {
+        let location = Location {
+            file: None,
+            chain: chain_name.to_string(),
+            chain_id: chain_id.to_string(),
+            position: None,
+        };
+        self.chain_errors.push((location, message));
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        let chain_errors = match severity {
+            Severity::Error => self.chain_errors.len(),
+            Severity::Warning => 0,
+        };
+        self.results
+            .iter()
+            .filter(|(_, diagnostic)| diagnostic.severity == severity)
+            .count()
+            + chain_errors
+    }
+
+    /// SARIF 2.1.0 log with one run. The scraped entry of every result is a physical location in
+    /// its file and a logical location `<chain>/<chain id>/<index>`, the plain location and diagnostic
+    /// fields are in its properties. Chain errors only have the logical location `<chain>/<chain id>`.
+    pub fn to_sarif(&self) -> Value {
+        let mut rules: BTreeSet<&str> = self
+            .results
+            .iter()
+            .map(|(_, diagnostic)| diagnostic.rule.as_str())
+            .collect();
+        if !self.chain_errors.is_empty() {
+            rules.insert(CHAIN_ERROR_RULE);
+        }
+        let chain_name = |location: &Location| format!("{}/{}", location.chain, location.chain_id);
+        let mut results: Vec<Value> = self
+            .results
+            .iter()
+            .map(|(location, diagnostic)| {
+                let mut qualified_name = chain_name(location);
+                let mut physical_location = json!({ "artifactLocation": { "uri": location.file } });
+                if let Some(position) = location.position {
+                    qualified_name = format!("{qualified_name}/{}", position.index);
+                    if let Some(line) = position.line {
+                        physical_location["region"] = json!({ "startLine": line });
+                    }
+                }
+                json!({
+                    "ruleId": diagnostic.rule,
+                    "level": diagnostic.severity,
+                    "message": { "text": diagnostic.message },
+                    "locations": [{
+                        "physicalLocation": physical_location,
+                        "logicalLocations": [{
+                            "name": diagnostic.key,
+                            "fullyQualifiedName": qualified_name,
+                            "kind": "element",
+                        }],
+                    }],
+                    "properties": {
+                        "location": location,
+                        "entity": diagnostic.entity,
+                        "key": diagnostic.key,
+                    },
+                })
+            })
+            .collect();
+        results.extend(self.chain_errors.iter().map(|(location, message)| {
+            json!({
+                "ruleId": CHAIN_ERROR_RULE,
+                "level": Severity::Error,
+                "message": { "text": message },
+                "locations": [{
+                    "logicalLocations": [{
+                        "name": location.chain_id,
+                        "fullyQualifiedName": chain_name(location),
+                        "kind": "namespace",
+                    }],
+                }],
+                "properties": { "location": location },
+            })
+        }));
+
+        json!({
+            "$schema": SARIF_SCHEMA,
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "update-ans",
+                        "rules": rules.into_iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+                    },
+                },
+                "results": results,
+            }],
+        })
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), AbstractInterfaceError> {
+        let path = path.as_ref();
+        let content = serde_json::to_string_pretty(&self.to_sarif())?;
+        std::fs::write(path, content).map_err(|e| {
+            CwOrchError::StdErr(format!("failed to write {}: {e}", path.display())).into()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn locates_diagnostics_at_their_entry() {
+        let positions = EntryPositions::from([(
+            EntityType::Assets,
+            HashMap::from([(
+                "juno>Juno".to_string(),
+                EntryPosition {
+                    index: 3,
+                    line: Some(21),
+                },
+            )]),
+        )]);
+        let mut report = ValidationReport::default();
+        report.add_chain(
+            "juno",
+            "juno-1",
+            vec![
+                Diagnostic::error(
+                    "asset-name-case",
+                    EntityType::Assets,
+                    "juno>Juno",
+                    "not lowercase".to_string(),
+                ),
+                Diagnostic::warning(
+                    "pool-dex-unlisted",
+                    EntityType::Pools,
+                    "juno1pool",
+                    "no rules".to_string(),
+                ),
+            ],
+            &positions,
+        );
+
+        let sarif = report.to_sarif();
+        let results = &sarif["runs"][0]["results"];
+        let physical_location = &results[0]["locations"][0]["physicalLocation"];
+
+        assert_eq!(report.count(Severity::Error), 1);
+        assert_eq!(results[0]["level"], "error");
+        assert_eq!(
+            results[0]["locations"][0]["logicalLocations"][0]["fullyQualifiedName"],
+            "juno/juno-1/3"
+        );
+        assert_eq!(
+            physical_location["artifactLocation"]["uri"],
+            "out/assets.json"
+        );
+        assert_eq!(physical_location["region"]["startLine"], 21);
+        assert_eq!(results[0]["properties"]["location"]["position"]["index"], 3);
+        assert_eq!(results[1]["level"], "warning");
+        assert_eq!(
+            results[1]["properties"]["location"]["position"],
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn chain_errors_have_no_file() {
+        let mut report = ValidationReport::default();
+        report.add_chain_error("juno", "juno-1", "no scraped section".to_string());
+
+        let sarif = report.to_sarif();
+        let result = &sarif["runs"][0]["results"][0];
+
+        assert_eq!(report.count(Severity::Error), 1);
+        assert_eq!(result["ruleId"], CHAIN_ERROR_RULE);
+        assert_eq!(result["locations"][0]["physicalLocation"], Value::Null);
+        assert_eq!(
+            result["locations"][0]["logicalLocations"][0]["fullyQualifiedName"],
+            "juno/juno-1"
+        );
+    }
+
+    #[test]
+    fn finds_the_lines_of_section_entries() {
+        let text = r#"{
+  "juno": {
+    "uni-6": [["juno>junox", {"native": "ujunox"}]],
+    "juno-1": [
+      [
+        "juno>juno",
+        { "native": "ujuno" }
+      ],
+      ["juno>neta", { "cw20": "juno1 \"neta\"" }],
+      [ "juno>raw", 6 ]
+    ]
+  },
+  "osmosis": { "juno-1": [["juno>osmo", {}]] }
+}"#;
+
+        assert_eq!(entry_lines(text, "juno", "juno-1"), vec![5, 9, 10]);
+        assert_eq!(entry_lines(text, "juno", "uni-6"), vec![3]);
+        assert!(entry_lines(text, "juno", "juno-2").is_empty());
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use abstract_core::objects::{PoolMetadata, UniquePoolId};
+use abstract_interface::{AbstractInterfaceError, AnsHost};
+use cw_orch::prelude::*;
+
+use crate::journal::pool_address_key;
+use crate::{AnsData, AnsDataDiff, EntityType};
+
+/// What is still out of sync on the ANS host after an update
+#[derive(Default, Debug)]
+pub struct VerificationReport {
+    /// Remaining diff between the scraped data and the on-chain entries
+    pub residual: AnsDataDiff,
+    /// Pool addresses registered under more than one pool id, with those ids
+    pub pool_addresses_with_several_ids: Vec<(String, Vec<UniquePoolId>)>,
+    /// Metadata that isn't attached to any pool address, left over from a partially applied pool update
+    pub orphaned_pool_metadata: Vec<(UniquePoolId, PoolMetadata)>,
+}
+
+impl VerificationReport {
+    pub fn is_in_sync(&self) -> bool {
+        self.residual.is_empty()
+            && self.pool_addresses_with_several_ids.is_empty()
+            && self.orphaned_pool_metadata.is_empty()
+    }
+}
+
+/// Query the ANS host again and compare it against the same scraped entries that were used for the update
+pub fn verify(
+    ans_host: &AnsHost<Daemon>,
+    scraped_entries: AnsData,
+    entities: &[EntityType],
+) -> Result<VerificationReport, AbstractInterfaceError> {
+    let on_chain_entries = crate::get_on_chain_entries(ans_host, entities)?;
+
+    let mut report = VerificationReport::default();
+    if entities.contains(&EntityType::Pools) {
+        let metadatas = crate::pools::get_on_chain_metadatas(ans_host)?;
+        let references: Vec<(String, UniquePoolId)> =
+            crate::pools::get_on_chain_pool_references(ans_host, &metadatas)?
+                .iter()
+                .map(|(address, id)| (pool_address_key(address), *id))
+                .collect();
+        report.orphaned_pool_metadata = orphaned_pool_metadata(&references, &metadatas);
+        report.pool_addresses_with_several_ids = several_ids_per_address(&references);
+    }
+    report.residual = crate::diff(scraped_entries, on_chain_entries)?;
+
+    Ok(report)
+}
+
+// Several pools can share their metadata, like osmosis pools of the same assets and pool type,
+// so pools are only broken when the references between ids and addresses don't line up
+fn several_ids_per_address(
+    references: &[(String, UniquePoolId)],
+) -> Vec<(String, Vec<UniquePoolId>)> {
+    let mut ids_per_address: BTreeMap<&String, Vec<UniquePoolId>> = BTreeMap::new();
+    for (address, id) in references {
+        let ids = ids_per_address.entry(address).or_default();
+        if !ids.contains(id) {
+            ids.push(*id);
+        }
+    }
+    ids_per_address
+        .into_iter()
+        .filter(|(_, ids)| ids.len() > 1)
+        .map(|(address, ids)| (address.clone(), ids))
+        .collect()
+}
+
+fn orphaned_pool_metadata(
+    references: &[(String, UniquePoolId)],
+    metadatas: &[(UniquePoolId, PoolMetadata)],
+) -> Vec<(UniquePoolId, PoolMetadata)> {
+    let referenced: HashSet<UniquePoolId> = references.iter().map(|(_, id)| *id).collect();
+    metadatas
+        .iter()
+        .filter(|(id, _)| !referenced.contains(id))
+        .cloned()
+        .collect()
+}
+
+impl fmt::Display for VerificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_in_sync() {
+            return writeln!(f, "ANS host is in sync with the scraped data");
+        }
+        let residual = &self.residual;
+        write_keys(f, "contracts not removed", residual.contracts.0.iter())?;
+        write_keys(
+            f,
+            "contracts not added or updated",
+            residual.contracts.1.keys(),
+        )?;
+        write_keys(f, "assets not removed", residual.assets.0.iter())?;
+        write_keys(f, "assets not added or updated", residual.assets.1.keys())?;
+        write_keys(f, "dexes not removed", residual.dexes.0.iter())?;
+        write_keys(f, "dexes not added", residual.dexes.1.keys())?;
+        write_keys(f, "pools not removed", residual.pools.0.iter())?;
+        write_keys(f, "pools not added or updated", residual.pools.1.keys())?;
+        write_keys(
+            f,
+            "pool addresses with several pool ids",
+            self.pool_addresses_with_several_ids.iter(),
+        )?;
+        write_keys(
+            f,
+            "pool metadata without pool address",
+            self.orphaned_pool_metadata.iter(),
+        )?;
+        Ok(())
+    }
+}
+
+fn write_keys<T: fmt::Debug>(
+    f: &mut fmt::Formatter<'_>,
+    label: &str,
+    keys: impl ExactSizeIterator<Item = T>,
+) -> fmt::Result {
+    if keys.len() == 0 {
+        return Ok(());
+    }
+    writeln!(f, "{} ({}):", label, keys.len())?;
+    for key in keys {
+        writeln!(f, "  - {:?}", key)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use abstract_core::objects::{AssetEntry, PoolType};
+
+    use super::*;
+
+    fn metadata(dex: &str) -> PoolMetadata {
+        PoolMetadata {
+            dex: dex.to_string(),
+            pool_type: PoolType::ConstantProduct,
+            assets: vec![AssetEntry::new("juno>juno"), AssetEntry::new("juno>atom")],
+        }
+    }
+
+    fn reference(address: &str, id: u64) -> (String, UniquePoolId) {
+        (address.to_string(), UniquePoolId::new(id))
+    }
+
+    #[test]
+    fn accepts_pools_sharing_metadata() {
+        // Two osmosis pools of the same assets and type, each listed under both pairings
+        let references = vec![
+            reference("1", 1),
+            reference("2", 2),
+            reference("1", 1),
+            reference("2", 2),
+        ];
+        let metadatas = vec![
+            (UniquePoolId::new(1), metadata("osmosis")),
+            (UniquePoolId::new(2), metadata("osmosis")),
+        ];
+
+        assert!(several_ids_per_address(&references).is_empty());
+        assert!(orphaned_pool_metadata(&references, &metadatas).is_empty());
+    }
+
+    #[test]
+    fn finds_addresses_with_several_ids() {
+        let references = vec![reference("1", 1), reference("2", 2), reference("1", 3)];
+
+        let several = several_ids_per_address(&references);
+
+        assert_eq!(
+            several,
+            vec![(
+                "1".to_string(),
+                vec![UniquePoolId::new(1), UniquePoolId::new(3)]
+            )]
+        );
+    }
+
+    #[test]
+    fn finds_orphaned_metadata() {
+        let references = vec![reference("1", 1)];
+        let metadatas = vec![
+            (UniquePoolId::new(1), metadata("osmosis")),
+            (UniquePoolId::new(2), metadata("osmosis")),
+        ];
+
+        let orphaned = orphaned_pool_metadata(&references, &metadatas);
+
+        assert_eq!(orphaned.len(), 1);
+        assert_eq!(orphaned[0].0, UniquePoolId::new(2));
+    }
+}